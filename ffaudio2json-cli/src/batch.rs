@@ -0,0 +1,230 @@
+use std::{
+	collections::HashSet,
+	ffi::OsStr,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+/// Extensions of the files that are picked up when searching a directory for inputs
+const AUDIO_EXTENSIONS: &[&str] = &[
+	"aac", "ac3", "aif", "aifc", "aiff", "alac", "amr", "ape", "caf", "dts", "flac", "m4a", "m4b", "mka", "mp2", "mp3", "mpc", "oga", "ogg", "opus",
+	"ra", "spx", "tta", "w64", "wav", "weba", "webm", "wma", "wv",
+];
+
+/// A file to generate a waveform for
+#[derive(Debug)]
+pub struct Input {
+	pub path: PathBuf,
+
+	/// The directory that the file is in, relative to the directory argument that it was found in
+	///
+	/// Outputs in an output directory are placed in the same relative directory, so that files with the same name don't clash.
+	pub relative_dir: PathBuf,
+}
+
+/// An input that couldn't be generated, or an argument that didn't resolve to any inputs
+#[derive(Debug)]
+pub struct Failure {
+	pub path: PathBuf,
+	pub error: String,
+}
+
+/// Resolves the input arguments to the files that they refer to
+///
+/// Directories are searched recursively for audio files, and arguments that don't exist are expanded as glob patterns.
+pub fn collect_inputs(args: &[PathBuf]) -> (Vec<Input>, Vec<Failure>) {
+	let mut inputs = Vec::new();
+	let mut failures = Vec::new();
+
+	for arg in args {
+		let paths = if arg.exists() {
+			vec![arg.clone()]
+		} else {
+			match expand_glob(arg) {
+				Ok(paths) if !paths.is_empty() => paths,
+
+				Ok(_) => {
+					failures.push(Failure {
+						path: arg.clone(),
+						error: "No such file, directory or matching glob pattern".to_string(),
+					});
+					continue;
+				}
+
+				Err(error) => {
+					failures.push(Failure { path: arg.clone(), error });
+					continue;
+				}
+			}
+		};
+
+		for path in paths {
+			if path.is_dir() {
+				if let Err(err) = search_dir(&path, Path::new(""), &mut inputs) {
+					failures.push(Failure {
+						path,
+						error: err.to_string(),
+					});
+				}
+			} else {
+				inputs.push(Input {
+					path,
+					relative_dir: PathBuf::new(),
+				});
+			}
+		}
+	}
+
+	// Arguments can overlap, for example a directory and a glob pattern inside of it
+	let mut seen = HashSet::new();
+	inputs.retain(|input| seen.insert(input.path.clone()));
+
+	(inputs, failures)
+}
+
+fn expand_glob(pattern: &Path) -> Result<Vec<PathBuf>, String> {
+	let pattern = pattern.to_str().ok_or("Glob patterns must be valid UTF-8")?;
+
+	// Like shells, wildcards don't match hidden files
+	let options = glob::MatchOptions {
+		require_literal_leading_dot: true,
+		..Default::default()
+	};
+
+	glob::glob_with(pattern, options)
+		.map_err(|err| format!("Invalid glob pattern: {err}"))?
+		.map(|path| path.map_err(|err| err.to_string()))
+		.collect()
+}
+
+/// Searches `dir` recursively for audio files, where `relative_dir` is the directory of `dir` relative to the directory argument that it's in
+pub fn search_dir(dir: &Path, relative_dir: &Path, inputs: &mut Vec<Input>) -> Result<(), std::io::Error> {
+	let mut entries = std::fs::read_dir(dir)?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	// Keep the order of the inputs, and so the order of the output, stable
+	entries.sort();
+
+	for path in entries {
+		let Some(name) = path.file_name() else { continue };
+
+		if is_hidden(name) {
+			continue;
+		}
+
+		if path.is_dir() {
+			search_dir(&path, &relative_dir.join(name), inputs)?;
+		} else if is_audio_file(&path) {
+			inputs.push(Input {
+				path,
+				relative_dir: relative_dir.to_path_buf(),
+			});
+		}
+	}
+
+	Ok(())
+}
+
+/// Whether the file or directory is skipped when searching for inputs, which includes the temporary files that outputs are generated in
+pub fn is_hidden(name: &OsStr) -> bool {
+	name.to_string_lossy().starts_with('.')
+}
+
+/// Whether the file has the extension of an audio format
+pub fn is_audio_file(path: &Path) -> bool {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+	/// The file name of the input
+	Name,
+
+	/// The file name of the input without its extension
+	Stem,
+
+	/// The extension of the output format, including the extension of the compression
+	Ext,
+
+	/// The channels that are computed, joined with dashes
+	Channels,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+	Literal(String),
+	Placeholder(Placeholder),
+}
+
+/// The file name of an output, with placeholders that are filled in for each input
+///
+/// The placeholders are `{name}`, `{stem}`, `{ext}` and `{channels}`.
+#[derive(Debug, Clone)]
+pub struct OutputTemplate(Vec<Part>);
+impl Default for OutputTemplate {
+	/// The same file name that's used for a single input, e.g. `song.mp3.json`
+	fn default() -> Self {
+		Self(vec![
+			Part::Placeholder(Placeholder::Name),
+			Part::Literal(".".to_string()),
+			Part::Placeholder(Placeholder::Ext),
+		])
+	}
+}
+impl FromStr for OutputTemplate {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = Vec::new();
+		let mut rest = s;
+
+		while let Some(start) = rest.find('{') {
+			if start != 0 {
+				parts.push(Part::Literal(rest[..start].to_string()));
+			}
+
+			let end = rest[start..].find('}').ok_or("Unclosed placeholder in output template")? + start;
+
+			parts.push(Part::Placeholder(match &rest[start + 1..end] {
+				"name" => Placeholder::Name,
+				"stem" => Placeholder::Stem,
+				"ext" => Placeholder::Ext,
+				"channels" => Placeholder::Channels,
+				placeholder => return Err(format!("Unknown placeholder {{{placeholder}}} in output template")),
+			}));
+
+			rest = &rest[end + 1..];
+		}
+
+		if !rest.is_empty() {
+			parts.push(Part::Literal(rest.to_string()));
+		}
+
+		if parts.is_empty() {
+			return Err("Output template must not be empty".to_string());
+		}
+
+		Ok(Self(parts))
+	}
+}
+impl OutputTemplate {
+	/// Fills in the placeholders for `input`, where `ext` and `channels` are the values of their placeholders
+	pub fn file_name(&self, input: &Path, ext: &str, channels: &str) -> String {
+		let mut file_name = String::new();
+
+		for part in &self.0 {
+			match part {
+				Part::Literal(literal) => file_name.push_str(literal),
+				Part::Placeholder(Placeholder::Name) => file_name.push_str(&input.file_name().unwrap_or_default().to_string_lossy()),
+				Part::Placeholder(Placeholder::Stem) => file_name.push_str(&input.file_stem().unwrap_or_default().to_string_lossy()),
+				Part::Placeholder(Placeholder::Ext) => file_name.push_str(ext),
+				Part::Placeholder(Placeholder::Channels) => file_name.push_str(channels),
+			}
+		}
+
+		file_name
+	}
+}
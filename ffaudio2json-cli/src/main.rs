@@ -1,22 +1,199 @@
+mod batch;
 mod options;
+mod preview;
+mod progress;
+mod watch;
 
+#[cfg(feature = "serve")]
+mod serve;
+
+use batch::{Failure, Input};
 use clap::Parser;
-use options::Options;
+use ffaudio2json::{FfAudio2Json, Report};
+use options::{Cli, Command, Options};
+use preview::Preview;
+use std::{collections::HashMap, path::Path, process::ExitCode};
+
+#[cfg(feature = "render")]
+use ffaudio2json::render::Renderer;
 
-fn main() -> Result<(), ffaudio2json::Error> {
-	let opt = Options::parse();
+fn main() -> ExitCode {
+	let cli = Cli::parse();
 
 	stderrlog::new()
 		.module("ffaudio2json")
 		.verbosity(log::Level::Debug)
 		.timestamp(stderrlog::Timestamp::Millisecond)
-		.quiet(opt.quiet)
+		.quiet(cli.quiet())
 		.init()
 		.ok();
 
-	let output_path = ffaudio2json::FfAudio2Json::try_from(opt).unwrap().run()?;
+	match &cli.command {
+		Some(Command::Watch(watch)) => watch::watch(watch),
+		#[cfg(feature = "serve")]
+		Some(Command::Serve(serve)) => serve::serve(serve),
+		None => generate_inputs(&cli.options),
+	}
+}
+
+/// Generates waveforms for the inputs once, and prints a summary if there was more than one
+fn generate_inputs(opt: &Options) -> ExitCode {
+	let (inputs, failures) = batch::collect_inputs(&opt.inputs);
+
+	// Errors are reported even when quiet, as they were when only a single input was accepted
+	for failure in &failures {
+		eprintln!("{}: {}", failure.path.display(), failure.error);
+	}
+
+	if inputs.len() > 1 && opt.output.is_some() {
+		eprintln!("--output can only be used with a single input, use --output-dir and --output-template instead");
+		return ExitCode::from(2);
+	}
+
+	#[cfg(feature = "render")]
+	if inputs.len() > 1 && opt.render.render.is_some() {
+		eprintln!("--render can only be used with a single input");
+		return ExitCode::from(2);
+	}
+
+	let mut summary = match generate(opt, &inputs) {
+		Ok(summary) => summary,
+		Err(err) => {
+			eprintln!("{err}");
+			return ExitCode::from(2);
+		}
+	};
+	summary.failures.splice(0..0, failures);
+
+	let Summary { generated, cached, failures } = summary;
+
+	// The failures were already printed as they happened
+	if !opt.quiet && generated + failures.len() > 1 {
+		let mut line = format!("Generated {generated} of {} waveforms", generated + failures.len());
+
+		if cached > 0 {
+			line += &format!(", {cached} of which were up to date");
+		}
+
+		if !failures.is_empty() {
+			line += &format!(", {} failed", failures.len());
+		}
+
+		eprintln!("{line}");
+	}
+
+	if failures.is_empty() {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	}
+}
+
+/// The outcome of generating waveforms for a batch of inputs
+#[derive(Debug, Default)]
+struct Summary {
+	generated: usize,
+
+	/// How many of the generated waveforms were skipped because they were up to date
+	cached: usize,
+
+	failures: Vec<Failure>,
+}
+
+/// Generates waveforms for `inputs`, printing each output and error as its result comes in
+///
+/// Fails without generating anything if the options are invalid for every input.
+fn generate(opt: &Options, inputs: &[Input]) -> Result<Summary, Box<dyn std::error::Error>> {
+	let preview = opt.preview();
+
+	#[cfg(feature = "render")]
+	let renderer = opt.render.renderer()?;
+
+	let mut failures = Vec::new();
+
+	let mut fail = |path: &Path, error: Box<dyn std::error::Error>| {
+		eprintln!("{}: {error}", path.display());
+
+		failures.push(Failure {
+			path: path.to_path_buf(),
+			error: error.to_string(),
+		});
+	};
+
+	// Inputs with the same name in different directories have the same output in --output-dir, and would overwrite each other's
+	let mut outputs = HashMap::<_, usize>::new();
+	for output in inputs.iter().filter_map(|input| opt.output_path(input)) {
+		*outputs.entry(output).or_default() += 1;
+	}
+
+	// The configurations are built up front, so that the inputs they're for are known when their results are reported
+	let mut configs = Vec::with_capacity(inputs.len());
+	let mut paths = Vec::with_capacity(inputs.len());
+
+	for input in inputs {
+		if let Some(output) = opt.output_path(input).filter(|output| outputs[output] > 1) {
+			fail(&input.path, format!("{} is also the output of another input", output.display()).into());
+			continue;
+		}
+
+		match opt.builder(input).map_err(Into::into).and_then(|builder| Ok(builder.build()?)) {
+			Ok(config) => {
+				configs.push(config);
+				paths.push(&input.path);
+			}
+			Err(error) => fail(&input.path, error),
+		}
+	}
+
+	let mut generated = 0;
+	let mut cached = 0;
+
+	FfAudio2Json::run_batch(configs, opt.jobs, |index, report| {
+		let result = report.map_err(Into::into).and_then(|report| {
+			show(
+				&report,
+				preview.as_ref(),
+				#[cfg(feature = "render")]
+				renderer.as_ref(),
+			)
+		});
+
+		match result {
+			Ok(true) => {
+				generated += 1;
+				cached += 1;
+			}
+			Ok(false) => generated += 1,
+			Err(error) => fail(paths[index], error),
+		}
+	});
+
+	Ok(Summary { generated, cached, failures })
+}
+
+/// Prints the path of a generated waveform, and previews or renders it if requested
+///
+/// Returns whether the output was up to date, so that it wasn't generated again.
+fn show(
+	report: &Report,
+	preview: Option<&Preview>,
+	#[cfg(feature = "render")] renderer: Option<&(std::path::PathBuf, Renderer)>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+	println!("{}", report.output.display());
+
+	if let Some(preview) = preview {
+		preview.print(
+			report.waveform.as_ref().ok_or("The points of the waveform weren't kept to preview")?,
+			&mut std::io::stdout().lock(),
+		)?;
+	}
+
+	#[cfg(feature = "render")]
+	if let Some((path, renderer)) = renderer {
+		renderer.render(report.waveform.as_ref().ok_or("The points of the waveform weren't kept to render")?, path)?;
 
-	println!("{}", output_path.display());
+		println!("{}", path.display());
+	}
 
-	Ok(())
+	Ok(report.cached)
 }
@@ -1,8 +1,106 @@
-use ffaudio2json::Channel;
-use std::path::PathBuf;
+use crate::{
+	batch::{Input, OutputTemplate},
+	preview::{ColorChoice, Preview, PreviewStyle},
+	progress::ProgressBar,
+};
+use ffaudio2json::{Channel, FfAudio2JsonBuilder, JsonStyle, OutputFormat, PointEncoding};
+use std::{
+	io::IsTerminal,
+	path::{Path, PathBuf},
+};
+
+#[cfg(feature = "compress")]
+use ffaudio2json::Compression;
+
+#[cfg(feature = "render")]
+use ffaudio2json::render::{Color, RenderStyle, Renderer, RendererBuilderError};
 
 #[derive(Debug, clap::Parser)]
 #[structopt(about = env!("CARGO_PKG_DESCRIPTION"), version = env!("CARGO_PKG_VERSION"), author = env!("CARGO_PKG_AUTHORS"))]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Option<Command>,
+
+	#[clap(flatten)]
+	pub options: Options,
+}
+impl Cli {
+	/// Whether all output of the command should be suppressed
+	pub fn quiet(&self) -> bool {
+		match &self.command {
+			Some(Command::Watch(watch)) => watch.options.quiet,
+			#[cfg(feature = "serve")]
+			Some(Command::Serve(serve)) => serve.quiet,
+			None => self.options.quiet,
+		}
+	}
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+	/// Generate waveforms for the files and directories given as inputs, then keep them up to date as files are added, modified or removed
+	Watch(Box<WatchOptions>),
+
+	#[cfg(feature = "serve")]
+	/// Serve an HTTP endpoint that generates waveforms of uploaded or local audio files on demand
+	Serve(ServeOptions),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct WatchOptions {
+	#[structopt(
+		long,
+		default_value = "500",
+		help = "Milliseconds to wait for changes to settle before regenerating waveforms"
+	)]
+	pub debounce: u64,
+
+	#[clap(flatten)]
+	pub options: Options,
+}
+
+#[cfg(feature = "serve")]
+#[derive(Debug, clap::Parser)]
+pub struct ServeOptions {
+	#[structopt(long, default_value = "127.0.0.1:8080", help = "Address to listen on")]
+	pub listen: String,
+
+	#[structopt(
+		long,
+		default_value = "4",
+		help = "Number of waveforms that are generated at once. Further requests wait for one to finish, while uploads are received alongside them. 0 uses the number of CPU cores"
+	)]
+	pub max_concurrent: usize,
+
+	#[structopt(
+		long,
+		default_value = "16",
+		help = "Number of requests that may wait for a waveform to be generated, including those whose uploads are being received. Further requests are rejected with 503 Service Unavailable"
+	)]
+	pub max_queued: usize,
+
+	#[structopt(
+		long,
+		default_value = "60",
+		help = "Seconds that receiving the upload and generating the waveform of a request may take before it fails"
+	)]
+	pub timeout: u64,
+
+	#[structopt(long, default_value = "512", help = "Maximum size of an uploaded audio file, in MiB")]
+	pub max_upload_size: u64,
+
+	#[structopt(
+		long,
+		help = "Directory that local audio files may be requested from with the path parameter. Can be repeated. Local files can't be requested without it"
+	)]
+	pub allow_dir: Vec<PathBuf>,
+
+	#[structopt(short, long, help = "Don't log requests", default_value = "false")]
+	pub quiet: bool,
+}
+
+#[derive(Debug, clap::Parser)]
 pub struct Options {
 	#[structopt(short, long, default_value = "800", help = "Number of samples to generate")]
 	pub samples: u32,
@@ -40,9 +138,157 @@ pub struct Options {
 	#[structopt(long, short, default_value = "false", help = "Do not include the version info banner in the output")]
 	pub no_header: bool,
 
-	#[structopt(short, long, help = "Name of output file, defaults to <name of inputfile>.json")]
+	#[structopt(long, default_value = "false", help = "Detect silent intervals and include them in the output")]
+	pub detect_silence: bool,
+
+	#[structopt(long, default_value = "-60", help = "Level in dBFS below which audio is considered silent")]
+	pub silence_threshold: f64,
+
+	#[structopt(long, default_value = "0.5", help = "Minimum duration in seconds of a silent interval")]
+	pub silence_min_duration: f64,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Detect silence on each channel separately, rather than only when all channels are silent"
+	)]
+	pub silence_per_channel: bool,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Exclude leading and trailing silence from the waveform. Not supported by the audiowaveform formats"
+	)]
+	pub trim_silence: bool,
+
+	#[structopt(long, default_value = "-60", help = "Level in dBFS below which leading and trailing audio is trimmed")]
+	pub trim_silence_threshold: f64,
+
+	#[structopt(
+		long,
+		default_value = "0.1",
+		help = "Amount of audio in seconds to keep before the first and after the last sample above the trim threshold"
+	)]
+	pub trim_silence_hold: f64,
+
+	#[structopt(long, default_value = "false", help = "Detect clipping and report the clipped regions in the output")]
+	pub detect_clipping: bool,
+
+	#[structopt(
+		long,
+		default_value = "0",
+		help = "For float formats, how close to full scale a sample must be to be considered clipped"
+	)]
+	pub clipping_epsilon: f64,
+
+	#[structopt(
+		long,
+		default_value = "3",
+		help = "Minimum number of consecutive samples at full scale that are reported as clipping"
+	)]
+	pub clipping_min_samples: u32,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Output a channel containing the number of clipped samples in each point of the waveform"
+	)]
+	pub clipped_channel: bool,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Compute statistics for each channel and include them in the output"
+	)]
+	pub stats: bool,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Compute the phase correlation between the left and right channels, for each point and for the whole file"
+	)]
+	pub correlation: bool,
+
+	#[structopt(
+		long,
+		default_value = "json",
+		help = "Format of the output file: json, dat, audiowaveform-json, float32, uint8, msgpack, cbor, csv, tsv, ndjson"
+	)]
+	pub format: OutputFormat,
+
+	#[structopt(
+		long,
+		default_value = "decimal",
+		help = "How points are written in the JSON output: decimal, integer, base64. Quantized encodings record their scale in the output"
+	)]
+	pub encoding: PointEncoding,
+
+	#[structopt(long, default_value = "compact", help = "Layout of the JSON output: compact, pretty, minified")]
+	pub json_style: JsonStyle,
+
+	#[structopt(long, default_value = "16", help = "Number of bits used to store each value in integer formats (8 or 16)")]
+	pub bits: u8,
+
+	#[structopt(long, default_value = "64", help = "Maximum number of points in each record of the ndjson format")]
+	pub chunk_size: u32,
+
+	#[cfg(feature = "compress")]
+	#[structopt(
+		long,
+		help = "Compress the output file: gzip, br, zstd. The extension of the compression is appended to the default output file name"
+	)]
+	pub compress: Option<Compression>,
+
+	#[structopt(
+		short,
+		long,
+		help = "Name of output file, defaults to <name of inputfile>.<format>. Only allowed with a single input"
+	)]
 	pub output: Option<PathBuf>,
 
+	#[structopt(
+		long,
+		visible_alias = "out",
+		help = "Directory to write the outputs to, in the same directories relative to any directory inputs. Defaults to next to each input"
+	)]
+	pub output_dir: Option<PathBuf>,
+
+	#[structopt(
+		long,
+		help = "File name of each output, with the placeholders {name}, {stem}, {ext} and {channels}. Defaults to {name}.{ext}"
+	)]
+	pub output_template: Option<OutputTemplate>,
+
+	#[structopt(long, default_value = "false", help = "Fail instead of replacing the output file if it already exists")]
+	pub no_overwrite: bool,
+
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Write ndjson output to the output file directly, so that its records can be read as they're generated. The output isn't replaced atomically"
+	)]
+	pub stream_in_place: bool,
+
+	#[cfg(feature = "cache")]
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Skip inputs whose outputs are up to date, according to a fingerprint of the input and options stored next to each output"
+	)]
+	pub cache: bool,
+
+	#[cfg(feature = "cache")]
+	#[structopt(long, help = "Directory to store the fingerprints in instead of next to the outputs. Implies --cache")]
+	pub cache_dir: Option<PathBuf>,
+
+	#[cfg(feature = "cache")]
+	#[structopt(
+		long,
+		default_value = "false",
+		help = "Generate outputs even if they are up to date, and update their fingerprints"
+	)]
+	pub force: bool,
+
 	#[structopt(long, help = "Channels to compute: left, right, mid, side, min, max", default_value = "left right")]
 	#[clap(value_parser, value_delimiter = ' ')]
 	pub channels: Vec<Channel>,
@@ -50,22 +296,227 @@ pub struct Options {
 	#[structopt(short, long, help = "Suppress all output", default_value = "false")]
 	pub quiet: bool,
 
-	pub input: PathBuf,
+	#[structopt(long, default_value = "false", help = "Don't draw a progress bar on stderr while the audio is decoded")]
+	pub no_progress: bool,
+
+	#[structopt(
+		short,
+		long,
+		default_value = "1",
+		help = "Number of inputs to generate waveforms for at once. 0 uses the number of CPU cores"
+	)]
+	pub jobs: usize,
+
+	#[structopt(
+		long,
+		default_value = "1",
+		help = "Number of segments that each input is split into and decoded in parallel. 0 uses the number of CPU cores. Ignored if any analysis is enabled"
+	)]
+	pub segments: u32,
+
+	#[structopt(long, default_value = "false", help = "Draw the waveform in the terminal once it has been generated")]
+	pub preview: bool,
+
+	#[structopt(long, default_value = "braille", help = "Characters used to draw the preview")]
+	#[clap(value_enum)]
+	pub preview_style: PreviewStyle,
+
+	#[structopt(long, default_value = "4", help = "Number of lines each channel takes up in the preview")]
+	pub preview_height: usize,
+
+	#[structopt(long, default_value = "auto", help = "Draw each channel of the preview in its own colour")]
+	#[clap(value_enum)]
+	pub preview_color: ColorChoice,
+
+	#[cfg(feature = "render")]
+	#[clap(flatten)]
+	pub render: RenderOptions,
+
+	#[structopt(
+		required = true,
+		help = "Audio files to generate waveforms for. Directories are searched recursively for audio files, and glob patterns are expanded"
+	)]
+	pub inputs: Vec<PathBuf>,
+}
+impl Options {
+	/// How to draw the waveform in the terminal, if a preview was requested
+	pub fn preview(&self) -> Option<Preview> {
+		self.preview.then_some(Preview {
+			style: self.preview_style,
+			height: self.preview_height,
+			color: self.preview_color,
+		})
+	}
+
+	/// The configuration for generating the waveform of `input`
+	///
+	/// Creates the directory that the output is placed in, if it doesn't exist yet.
+	pub fn builder(&self, input: &Input) -> Result<FfAudio2JsonBuilder, std::io::Error> {
+		let output = self.output_path(input);
+		if let Some(dir) = output.as_deref().and_then(Path::parent).filter(|dir| !dir.as_os_str().is_empty()) {
+			std::fs::create_dir_all(dir)?;
+		}
+
+		let mut builder = FfAudio2JsonBuilder::default();
+
+		// The points are kept in memory so that they can be previewed or rendered once the waveform has been generated
+		builder.keep_points(self.preview);
+
+		#[cfg(feature = "render")]
+		if self.render.render.is_some() {
+			builder.keep_points(true);
+		}
+
+		#[cfg(feature = "compress")]
+		builder.compress(self.compress);
+
+		#[cfg(feature = "cache")]
+		builder
+			.cache(self.cache || self.cache_dir.is_some())
+			.cache_dir(self.cache_dir.clone())
+			.force(self.force);
+
+		// The progress of concurrent jobs can't be drawn on a single line
+		if !self.quiet && !self.no_progress && self.jobs == 1 && std::io::stderr().is_terminal() {
+			let bar = ProgressBar::default();
+			builder.progress(move |progress| bar.update(progress));
+		}
+
+		builder
+			.samples(self.samples)
+			.db_min(self.db_min)
+			.db_max(self.db_max)
+			.db_scale(self.db_scale)
+			.precision(self.precision)
+			.no_header(self.no_header)
+			.detect_silence(self.detect_silence)
+			.silence_threshold(self.silence_threshold)
+			.silence_min_duration(self.silence_min_duration)
+			.silence_per_channel(self.silence_per_channel)
+			.trim_silence(self.trim_silence)
+			.trim_silence_threshold(self.trim_silence_threshold)
+			.trim_silence_hold(self.trim_silence_hold)
+			.detect_clipping(self.detect_clipping)
+			.clipping_epsilon(self.clipping_epsilon)
+			.clipping_min_samples(self.clipping_min_samples)
+			.clipped_channel(self.clipped_channel)
+			.stats(self.stats)
+			.correlation(self.correlation)
+			.format(self.format)
+			.encoding(self.encoding)
+			.json_style(self.json_style)
+			.bits(self.bits)
+			.chunk_size(self.chunk_size)
+			.segments(self.segments)
+			.no_overwrite(self.no_overwrite)
+			.stream_in_place(self.stream_in_place)
+			.output(output)
+			.input(input.path.clone())
+			.channels(self.channels.clone());
+
+		Ok(builder)
+	}
+
+	/// Where the output of `input` is written, or `None` for the default file name next to the input
+	pub fn output_path(&self, input: &Input) -> Option<PathBuf> {
+		if self.output.is_some() {
+			return self.output.clone();
+		}
+
+		if self.output_dir.is_none() && self.output_template.is_none() {
+			return None;
+		}
+
+		let ext = self.format.extension().to_string();
+
+		#[cfg(feature = "compress")]
+		let ext = match self.compress {
+			Some(compression) => format!("{ext}.{}", compression.extension()),
+			None => ext,
+		};
+
+		let channels = self.channels.iter().map(ToString::to_string).collect::<Vec<_>>().join("-");
+
+		let default = OutputTemplate::default();
+		let file_name = self.output_template.as_ref().unwrap_or(&default).file_name(&input.path, &ext, &channels);
+
+		let dir = match &self.output_dir {
+			Some(output_dir) => output_dir.join(&input.relative_dir),
+			None => input.path.parent().map(Path::to_path_buf).unwrap_or_default(),
+		};
+
+		Some(dir.join(file_name))
+	}
 }
-impl TryFrom<Options> for ffaudio2json::FfAudio2Json {
-	type Error = ffaudio2json::FfAudio2JsonBuilderError;
-
-	fn try_from(val: Options) -> Result<Self, Self::Error> {
-		ffaudio2json::FfAudio2JsonBuilder::default()
-			.samples(val.samples)
-			.db_min(val.db_min)
-			.db_max(val.db_max)
-			.db_scale(val.db_scale)
-			.precision(val.precision)
-			.no_header(val.no_header)
-			.output(val.output)
-			.input(val.input)
-			.channels(val.channels)
-			.build()
+
+#[cfg(feature = "render")]
+#[derive(Debug, clap::Args)]
+pub struct RenderOptions {
+	#[arg(long, help = "Also render the waveform to an image. The format is chosen by the extension: png or svg")]
+	pub render: Option<PathBuf>,
+
+	#[arg(long, default_value = "1800", help = "Width of the rendered image, in pixels at 96 DPI")]
+	pub render_width: u32,
+
+	#[arg(long, default_value = "280", help = "Height of the rendered image, in pixels at 96 DPI")]
+	pub render_height: u32,
+
+	#[arg(long, default_value = "bars", help = "How the points are drawn in the rendered image: bars, line, filled")]
+	pub render_style: RenderStyle,
+
+	#[arg(
+		long,
+		help = "Colours of the channels in the rendered image as #rrggbb or #rrggbbaa, repeated if there are more channels than colours"
+	)]
+	#[clap(value_parser, value_delimiter = ' ')]
+	pub render_colors: Vec<Color>,
+
+	#[arg(long, help = "Background colour of the rendered image as #rrggbb or #rrggbbaa, transparent if not set")]
+	pub render_background: Option<Color>,
+
+	#[arg(
+		long,
+		default_value = "false",
+		help = "Draw each channel upwards from the bottom of its lane, rather than mirrored around its centre line"
+	)]
+	pub render_unmirrored: bool,
+
+	#[arg(
+		long,
+		default_value = "2",
+		help = "Width of each bar, or the thickness of the line, in the rendered image"
+	)]
+	pub render_bar_width: u32,
+
+	#[arg(long, default_value = "1", help = "Space between bars and between channels in the rendered image")]
+	pub render_gap: u32,
+
+	#[arg(long, default_value = "96", help = "Resolution of the rendered PNG image")]
+	pub render_dpi: f64,
+}
+#[cfg(feature = "render")]
+impl RenderOptions {
+	/// The path and configuration of the image to render, if rendering was requested
+	pub fn renderer(&self) -> Result<Option<(PathBuf, Renderer)>, RendererBuilderError> {
+		let Some(path) = self.render.clone() else {
+			return Ok(None);
+		};
+
+		let mut builder = Renderer::builder();
+		builder
+			.width(self.render_width)
+			.height(self.render_height)
+			.style(self.render_style)
+			.background(self.render_background)
+			.mirrored(!self.render_unmirrored)
+			.bar_width(self.render_bar_width)
+			.gap(self.render_gap)
+			.dpi(self.render_dpi);
+
+		if !self.render_colors.is_empty() {
+			builder.colors(self.render_colors.clone());
+		}
+
+		Ok(Some((path, builder.build()?)))
 	}
 }
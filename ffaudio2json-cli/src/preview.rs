@@ -0,0 +1,259 @@
+//! Draws a generated waveform in the terminal
+
+use ffaudio2json::Waveform;
+use std::io::{IsTerminal, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PreviewStyle {
+	/// Braille characters, with 2x4 dots per character
+	Braille,
+
+	/// Block characters, with 1x2 blocks per character
+	Block,
+}
+impl PreviewStyle {
+	/// The number of dots in each character, horizontally and vertically
+	fn resolution(self) -> (usize, usize) {
+		match self {
+			PreviewStyle::Braille => (2, 4),
+			PreviewStyle::Block => (1, 2),
+		}
+	}
+
+	/// The character that draws the dots set in `dots`, indexed by column then row
+	fn char(self, dots: [[bool; 4]; 2]) -> char {
+		match self {
+			PreviewStyle::Braille => {
+				const BITS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+				let bits = (0..2)
+					.flat_map(|x| (0..4).map(move |y| (x, y)))
+					.filter(|&(x, y)| dots[x][y])
+					.fold(0, |bits, (x, y)| bits | BITS[x][y]);
+
+				char::from_u32(0x2800 + bits).unwrap()
+			}
+
+			PreviewStyle::Block => match dots[0] {
+				[true, true, ..] => '█',
+				[true, false, ..] => '▀',
+				[false, true, ..] => '▄',
+				_ => ' ',
+			},
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+	/// Colour the preview if stdout is a terminal and `NO_COLOR` is not set
+	Auto,
+
+	/// Always colour the preview
+	Always,
+
+	/// Never colour the preview
+	Never,
+}
+impl ColorChoice {
+	fn enabled(self) -> bool {
+		match self {
+			ColorChoice::Auto => std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+			ColorChoice::Always => true,
+			ColorChoice::Never => false,
+		}
+	}
+}
+
+/// ANSI colours that channels are drawn in, repeated if there are more channels
+const COLORS: [u8; 6] = [36, 35, 33, 32, 34, 31];
+
+/// Width of the preview if the width of the terminal can't be determined
+const DEFAULT_WIDTH: usize = 80;
+
+/// Minimum number of characters between the labels of the time ruler
+const RULER_SPACING: usize = 12;
+
+pub struct Preview {
+	pub style: PreviewStyle,
+	pub height: usize,
+	pub color: ColorChoice,
+}
+impl Preview {
+	pub fn print(&self, waveform: &Waveform, output: &mut impl Write) -> Result<(), std::io::Error> {
+		let terminal_width = terminal_size::terminal_size()
+			.map(|(width, _)| width.0 as usize)
+			.or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+			.unwrap_or(DEFAULT_WIDTH);
+
+		let labels = waveform.channels.iter().map(|(channel, _)| channel.to_string()).collect::<Vec<_>>();
+		let label_width = labels.iter().map(String::len).max().unwrap_or(0) + 1;
+		let width = terminal_width.saturating_sub(label_width).max(1);
+
+		let color = self.color.enabled();
+		let (dots_x, dots_y) = self.style.resolution();
+		let dot_height = self.height.max(1) * dots_y;
+
+		for (i, ((_, points), label)) in waveform.channels.iter().zip(&labels).enumerate() {
+			let values = resample(points, width * dots_x);
+
+			// The number of dots above and below the centre line in each column of dots
+			let extents = values
+				.iter()
+				.map(|value| {
+					let extent = (value.clamp(0.0, 1.0) * dot_height as f64 / 2.0).round() as usize;
+					if *value > 0.0 {
+						extent.max(1)
+					} else {
+						extent
+					}
+				})
+				.collect::<Vec<_>>();
+
+			let is_set = |x: usize, y: usize| {
+				let extent = extents.get(x).copied().unwrap_or(0);
+				y + extent >= dot_height / 2 && y < dot_height / 2 + extent
+			};
+
+			for row in 0..self.height.max(1) {
+				let line = (0..width)
+					.map(|column| {
+						let mut dots = [[false; 4]; 2];
+						for (x, dots) in dots.iter_mut().enumerate().take(dots_x) {
+							for (y, dot) in dots.iter_mut().enumerate().take(dots_y) {
+								*dot = is_set(column * dots_x + x, row * dots_y + y);
+							}
+						}
+						self.style.char(dots)
+					})
+					.collect::<String>();
+
+				let label = if row == 0 { label.as_str() } else { "" };
+
+				if color {
+					writeln!(output, "{label:label_width$}\x1b[{}m{line}\x1b[0m", COLORS[i % COLORS.len()])?;
+				} else {
+					writeln!(output, "{label:label_width$}{line}")?;
+				}
+			}
+		}
+
+		print_ruler(waveform.start, waveform.duration, label_width, width, output)
+	}
+}
+
+/// Prints tick marks and their times below a waveform that starts at `start` and lasts `duration` seconds
+fn print_ruler(start: f64, duration: f64, label_width: usize, width: usize, output: &mut impl Write) -> Result<(), std::io::Error> {
+	let mut ticks = vec![' '; width];
+	let mut times = vec![' '; width];
+
+	let mut column = 0;
+	while column < width {
+		let time = start + duration * column as f64 / width as f64;
+		let text = format_time(time, start + duration);
+
+		if column + text.len() > width {
+			break;
+		}
+
+		ticks[column] = '|';
+		times[column..column + text.len()]
+			.iter_mut()
+			.zip(text.chars())
+			.for_each(|(c, text)| *c = text);
+
+		column += (text.len() + 1).max(RULER_SPACING);
+	}
+
+	writeln!(output, "{:label_width$}{}", "", ticks.into_iter().collect::<String>().trim_end())?;
+	writeln!(output, "{:label_width$}{}", "", times.into_iter().collect::<String>().trim_end())
+}
+
+/// Formats a time in seconds, with more precision for shorter audio
+pub(crate) fn format_time(time: f64, end: f64) -> String {
+	if end < 60.0 {
+		format!("{time:.1}s")
+	} else {
+		let seconds = time.floor() as u64;
+		if end < 3600.0 {
+			format!("{}:{:02}", seconds / 60, seconds % 60)
+		} else {
+			format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+		}
+	}
+}
+
+/// Resamples the points to `len` values, taking the maximum of the points that fall into each value, or repeating points if there are fewer points than values
+fn resample(points: &[f64], len: usize) -> Vec<f64> {
+	if points.is_empty() {
+		return Vec::new();
+	}
+
+	(0..len)
+		.map(|i| {
+			let start = i * points.len() / len;
+			let end = ((i + 1) * points.len() / len).max(start + 1);
+			points[start..end].iter().copied().fold(0.0, f64::max)
+		})
+		.collect()
+}
+
+#[test]
+fn test_resample() {
+	// Fewer values than points keep the maximum of each group of points
+	assert_eq!(resample(&[0.1, 0.5, 0.2, 0.9], 2), [0.5, 0.9]);
+	assert_eq!(resample(&[1.0, 2.0, 3.0], 2), [1.0, 3.0]);
+
+	// More values than points repeat the points
+	assert_eq!(resample(&[0.1, 0.2], 4), [0.1, 0.1, 0.2, 0.2]);
+
+	assert_eq!(resample(&[0.3, 0.4], 2), [0.3, 0.4]);
+	assert!(resample(&[], 4).is_empty());
+}
+
+#[test]
+fn test_format_time() {
+	assert_eq!(format_time(0.0, 30.0), "0.0s");
+	assert_eq!(format_time(5.3, 30.0), "5.3s");
+	assert_eq!(format_time(75.9, 120.0), "1:15");
+	assert_eq!(format_time(3725.0, 7200.0), "1:02:05");
+}
+
+#[test]
+fn test_char() {
+	let mut dots = [[false; 4]; 2];
+	assert_eq!(PreviewStyle::Braille.char(dots), '\u{2800}');
+	assert_eq!(PreviewStyle::Block.char(dots), ' ');
+
+	dots[0][0] = true;
+	assert_eq!(PreviewStyle::Braille.char(dots), '⠁');
+	assert_eq!(PreviewStyle::Block.char(dots), '▀');
+
+	dots[0][1] = true;
+	assert_eq!(PreviewStyle::Block.char(dots), '█');
+
+	dots[0][0] = false;
+	assert_eq!(PreviewStyle::Block.char(dots), '▄');
+
+	dots = [[false; 4]; 2];
+	dots[1][3] = true;
+	assert_eq!(PreviewStyle::Braille.char(dots), '⢀');
+
+	assert_eq!(PreviewStyle::Braille.char([[true; 4]; 2]), '⣿');
+}
+
+#[test]
+fn test_print_ruler() {
+	let mut output = Vec::new();
+	print_ruler(0.0, 10.0, 2, 30, &mut output).unwrap();
+
+	assert_eq!(
+		String::from_utf8(output).unwrap(),
+		"  |           |           |\n  0.0s        4.0s        8.0s\n"
+	);
+
+	// Labels that wouldn't fit in the width are left out
+	let mut output = Vec::new();
+	print_ruler(0.0, 10.0, 0, 6, &mut output).unwrap();
+	assert_eq!(String::from_utf8(output).unwrap(), "|\n0.0s\n");
+}
@@ -0,0 +1,62 @@
+use crate::preview::format_time;
+use ffaudio2json::Progress;
+use std::{
+	io::Write,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+/// How often the progress bar is redrawn
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The widest that the bar itself is drawn
+const MAX_BAR_WIDTH: usize = 40;
+
+/// Draws the progress of the generator as a bar on a single line of stderr
+#[derive(Default)]
+pub struct ProgressBar {
+	/// When the bar was last drawn
+	drawn: Mutex<Option<Instant>>,
+}
+impl ProgressBar {
+	pub fn update(&self, progress: &Progress) {
+		let mut drawn = self.drawn.lock().unwrap();
+
+		if progress.fraction >= 1.0 {
+			// Clear the line, so that anything printed afterwards starts on an empty line
+			if drawn.take().is_some() {
+				eprint!("\r\x1b[2K");
+			}
+			return;
+		}
+
+		if drawn.is_some_and(|drawn| drawn.elapsed() < REDRAW_INTERVAL) {
+			return;
+		}
+		*drawn = Some(Instant::now());
+
+		let terminal_width = terminal_size::terminal_size_of(std::io::stderr())
+			.map(|(width, _)| width.0 as usize)
+			.unwrap_or(80);
+
+		// The total duration can be estimated from how far through the stream we are
+		let duration = if progress.fraction > 0.0 {
+			progress.time / progress.fraction
+		} else {
+			0.0
+		};
+		let status = format!(
+			" {:>3}% {} / {}",
+			(progress.fraction * 100.0).floor() as u32,
+			format_time(progress.time, duration),
+			format_time(duration, duration),
+		);
+
+		let width = terminal_width.saturating_sub(status.len() + 3).min(MAX_BAR_WIDTH);
+		let filled = ((progress.fraction * width as f64).round() as usize).min(width);
+
+		let mut stderr = std::io::stderr().lock();
+		write!(stderr, "\r[{}{}]{status}\x1b[K", "#".repeat(filled), " ".repeat(width - filled)).ok();
+		stderr.flush().ok();
+	}
+}
@@ -0,0 +1,819 @@
+use crate::options::ServeOptions;
+use ffaudio2json::{Channel, Error, FfAudio2JsonBuilder, OutputFormat};
+use std::{
+	fs::File,
+	io::{ErrorKind, Read, Write},
+	net::{TcpListener, TcpStream},
+	path::{Path, PathBuf},
+	process::ExitCode,
+	str::FromStr,
+	sync::{
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+		mpsc::{self, RecvTimeoutError},
+		Arc, Condvar, Mutex, MutexGuard,
+	},
+	time::{Duration, Instant},
+};
+use tempfile::TempPath;
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server, StatusCode};
+
+/// How long a client may send nothing before reading from its connection fails, unless the timeout of requests is shorter
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often an output that is being streamed is checked for more data once everything that was written has been sent
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Serves waveforms over HTTP until the process is killed
+///
+/// `POST /waveform` generates the waveform of the audio file in the body of the request, and `GET /waveform?path=...` generates the
+/// waveform of a local audio file in one of the allowed directories. Both accept the `samples`, `channels`, `db_min`, `db_max`,
+/// `db_scale` and `format` query parameters, and respond with the output file. NDJSON is streamed as it's generated.
+pub fn serve(options: &ServeOptions) -> ExitCode {
+	// Paths are canonicalized so that they can't escape the allowed directories through `..` or symlinks
+	let mut allowed_dirs = Vec::with_capacity(options.allow_dir.len());
+	for dir in &options.allow_dir {
+		match dir.canonicalize() {
+			Ok(dir) => allowed_dirs.push(dir),
+			Err(err) => {
+				eprintln!("{}: {err}", dir.display());
+				return ExitCode::FAILURE;
+			}
+		}
+	}
+
+	let temp_dir = match private_temp_dir() {
+		Ok(temp_dir) => temp_dir,
+		Err(err) => {
+			eprintln!("Failed to create a temporary directory: {err}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let idle_timeout = IDLE_TIMEOUT.min(Duration::from_secs(options.timeout.max(1)));
+	let server = match listen(&options.listen, idle_timeout)
+		.map_err(Into::into)
+		.and_then(|listener| Server::from_listener(listener, None))
+	{
+		Ok(server) => server,
+		Err(err) => {
+			eprintln!("Failed to listen on {}: {err}", options.listen);
+			return ExitCode::FAILURE;
+		}
+	};
+
+	if options.max_queued == 0 {
+		eprintln!("--max-queued must be at least 1");
+		return ExitCode::FAILURE;
+	}
+
+	let workers = match options.max_concurrent {
+		0 => std::thread::available_parallelism().map_or(1, |workers| workers.get()),
+		workers => workers,
+	};
+
+	if !options.quiet {
+		eprintln!("Listening on http://{}", server.server_addr());
+	}
+
+	// Requests are counted from when they're accepted until a worker takes them from the queue, including while their uploads are received.
+	// As no more than `max_queued` are counted, the queue never fills up and sending to it never blocks.
+	let (jobs, queue) = mpsc::sync_channel::<Job>(options.max_queued);
+	let queue = Mutex::new(queue);
+	let queued = AtomicUsize::new(0);
+	let (temp_dir, allowed_dirs, queued) = (temp_dir.path(), allowed_dirs.as_slice(), &queued);
+
+	std::thread::scope(|scope| {
+		// Jobs queue up until one of the workers is free to generate their waveforms
+		for _ in 0..workers {
+			scope.spawn(|| loop {
+				// The lock is released once a job is received, so that the other workers can wait for the next one
+				let job = queue.lock().unwrap().recv();
+				match job {
+					Ok(job) => {
+						queued.fetch_sub(1, Ordering::Relaxed);
+						handle(job, options, temp_dir);
+					}
+					Err(_) => break,
+				}
+			});
+		}
+
+		// Uploads are received by threads of their own, so that slow clients can't keep the workers from generating waveforms.
+		// Idle clients can't keep them waiting for longer than the idle timeout either, as reading from their connections fails.
+		loop {
+			let request = match server.recv() {
+				Ok(request) => request,
+				Err(err) => {
+					eprintln!("Failed to receive request: {err}");
+					break;
+				}
+			};
+
+			let start = Instant::now();
+
+			let (builder, format) = match params(&request, allowed_dirs) {
+				Ok(params) => params,
+				Err(err) => {
+					respond(request, error_response(&err), start, options.quiet);
+					continue;
+				}
+			};
+
+			if !try_queue(queued, options.max_queued) {
+				respond(
+					request,
+					error_response(&HttpError::new(503, "Too many requests are waiting, try again later")),
+					start,
+					options.quiet,
+				);
+				continue;
+			}
+
+			let mut job = Job {
+				request,
+				start,
+				builder,
+				format,
+				upload: None,
+			};
+
+			if *job.request.method() != Method::Post {
+				jobs.send(job).ok();
+				continue;
+			}
+
+			let jobs = jobs.clone();
+			scope.spawn(move || {
+				let deadline = start + Duration::from_secs(options.timeout);
+				match receive_upload(&mut job.request, temp_dir, options.max_upload_size * 1024 * 1024, deadline) {
+					Ok(upload) => {
+						job.builder.input(upload.to_path_buf());
+						job.upload = Some(upload);
+						jobs.send(job).ok();
+					}
+					Err(err) => {
+						queued.fetch_sub(1, Ordering::Relaxed);
+						respond(job.request, error_response(&err), start, options.quiet);
+					}
+				}
+			});
+		}
+
+		// The workers stop once the jobs that are queued or being uploaded are done
+		drop(jobs);
+	});
+
+	// The server only stops if it fails
+	ExitCode::FAILURE
+}
+
+/// Listens on `address`, where reading from the connections that are accepted fails once their clients have sent nothing for `idle_timeout`
+fn listen(address: &str, idle_timeout: Duration) -> Result<TcpListener, std::io::Error> {
+	let listener = TcpListener::bind(address)?;
+
+	// Connections inherit the read timeout of the socket they're accepted from. `TcpListener` can't set one itself, so it's set through
+	// a stream that shares its socket.
+	#[cfg(unix)]
+	let socket = TcpStream::from(std::os::fd::OwnedFd::from(listener.try_clone()?));
+	#[cfg(windows)]
+	let socket = TcpStream::from(std::os::windows::io::OwnedSocket::from(listener.try_clone()?));
+	socket.set_read_timeout(Some(idle_timeout))?;
+
+	Ok(listener)
+}
+
+/// Creates the directory that uploads and outputs are written to, which only this user can access, under a name that can't be predicted
+fn private_temp_dir() -> Result<tempfile::TempDir, std::io::Error> {
+	let mut builder = tempfile::Builder::new();
+	builder.prefix("ffaudio2json-serve.");
+
+	// The temporary directory of Windows is already private to each user
+	#[cfg(unix)]
+	builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+
+	builder.tempdir()
+}
+
+/// Counts a request towards those that are waiting for a worker, unless `max_queued` of them already are
+fn try_queue(queued: &AtomicUsize, max_queued: usize) -> bool {
+	queued
+		.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| (queued < max_queued).then_some(queued + 1))
+		.is_ok()
+}
+
+/// An error that is sent as the response to a request
+#[derive(Debug)]
+struct HttpError {
+	status: u16,
+	message: String,
+}
+impl HttpError {
+	fn new(status: u16, message: impl Into<String>) -> Self {
+		Self {
+			status,
+			message: message.into(),
+		}
+	}
+}
+impl From<Error> for HttpError {
+	fn from(err: Error) -> Self {
+		match err {
+			Error::Cancelled => Self::new(503, "Timed out while generating the waveform"),
+
+			// The input couldn't be decoded
+			Error::Ffmpeg(_) | Error::UnsupportedFormat { .. } => Self::new(422, err.to_string()),
+
+			_ => Self::new(500, err.to_string()),
+		}
+	}
+}
+
+/// A request whose input is ready for its waveform to be generated
+struct Job {
+	request: Request,
+	start: Instant,
+	builder: FfAudio2JsonBuilder,
+	format: OutputFormat,
+
+	/// The audio file that was uploaded, which is removed once the response has been sent
+	upload: Option<TempPath>,
+}
+
+/// The waveform of a request while it's being generated, which the response reads its output alongside
+#[derive(Default)]
+struct Generation {
+	state: Mutex<GenerationState>,
+	changed: Condvar,
+
+	/// Cancels generation once the deadline has passed, or the response has failed
+	cancel: Arc<AtomicBool>,
+}
+impl Generation {
+	/// Marks the output as ready to be streamed, once generation has started successfully
+	fn stream(&self) {
+		let mut state = self.state.lock().unwrap();
+		if matches!(*state, GenerationState::Starting) {
+			*state = GenerationState::Streaming;
+			self.changed.notify_all();
+		}
+	}
+
+	fn finish(&self, result: Result<(), HttpError>) {
+		*self.state.lock().unwrap() = GenerationState::Finished(result);
+		self.changed.notify_all();
+	}
+
+	/// Waits until the output can be streamed, or generation has finished
+	fn started(&self) -> MutexGuard<'_, GenerationState> {
+		self.changed
+			.wait_while(self.state.lock().unwrap(), |state| matches!(state, GenerationState::Starting))
+			.unwrap()
+	}
+}
+
+#[derive(Default)]
+enum GenerationState {
+	/// Nothing has been written yet, so the request can still fail with an error response
+	#[default]
+	Starting,
+
+	/// The output is being written in place, and can be sent as it grows
+	Streaming,
+
+	Finished(Result<(), HttpError>),
+}
+
+/// Reads the output of a [`Generation`], waiting for more to be written until it has finished
+struct Tail {
+	file: File,
+	generation: Arc<Generation>,
+}
+impl Read for Tail {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		loop {
+			// The state is checked before reading, so that everything that was written before generation finished is read
+			let finished = match &*self.generation.state.lock().unwrap() {
+				GenerationState::Finished(Ok(())) => true,
+
+				// The response is cut short, so that the client can tell that it's incomplete
+				GenerationState::Finished(Err(err)) => return Err(std::io::Error::other(err.message.clone())),
+
+				_ => false,
+			};
+
+			let read = self.file.read(buf)?;
+			if read > 0 || finished {
+				return Ok(read);
+			}
+
+			std::thread::sleep(POLL_INTERVAL);
+		}
+	}
+}
+
+fn handle(job: Job, options: &ServeOptions, temp_dir: &Path) {
+	let Job {
+		request,
+		start,
+		builder,
+		format,
+		upload,
+	} = job;
+
+	let deadline = start + Duration::from_secs(options.timeout);
+
+	// The output is kept until the response has been sent, as it is read from while it is sent
+	let output = match tempfile::Builder::new()
+		.prefix("output.")
+		.suffix(&format!(".{}", format.extension()))
+		.tempfile_in(temp_dir)
+	{
+		Ok(output) => output.into_temp_path(),
+		Err(err) => return respond(request, error_response(&Error::from(err).into()), start, options.quiet),
+	};
+
+	let generation = Arc::new(Generation::default());
+
+	std::thread::scope(|scope| {
+		scope.spawn(|| generation.finish(generate(builder, format, &output, deadline, &generation)));
+
+		respond(request, stream_response(&generation, &output, format), start, options.quiet);
+
+		// Stop generating if the client went away before the response was complete
+		generation.cancel.store(true, Ordering::Relaxed);
+	});
+
+	drop((output, upload));
+}
+
+/// Responds with the output once it can be streamed, or with the error that generating it failed with
+fn stream_response(generation: &Arc<Generation>, output: &Path, format: OutputFormat) -> ResponseBox {
+	let complete = match &*generation.started() {
+		GenerationState::Finished(Err(err)) => return error_response(err),
+		GenerationState::Finished(Ok(())) => true,
+		_ => false,
+	};
+
+	let file = match File::open(output) {
+		Ok(file) => file,
+		Err(err) => return error_response(&Error::from(err).into()),
+	};
+
+	// Complete outputs are sent with their length, while outputs that are still growing are sent in chunks as they're written
+	let length = complete.then(|| file.metadata().ok()).flatten().map(|metadata| metadata.len() as usize);
+
+	Response::new(
+		StatusCode(200),
+		vec![Header::from_bytes("Content-Type", format.mime_type()).unwrap()],
+		Tail {
+			file,
+			generation: generation.clone(),
+		},
+		length,
+		None,
+	)
+	.boxed()
+}
+
+fn respond(request: Request, response: ResponseBox, start: Instant, quiet: bool) {
+	let status = response.status_code().0;
+	let method = request.method().clone();
+	let url = request.url().to_string();
+
+	if let Err(err) = request.respond(response) {
+		eprintln!("{method} {url}: Failed to send response: {err}");
+	}
+
+	if !quiet {
+		eprintln!("{method} {url} {status} ({:?})", start.elapsed());
+	}
+}
+
+fn error_response(err: &HttpError) -> ResponseBox {
+	Response::from_string(format!("{}\n", err.message))
+		.with_status_code(err.status)
+		.with_header(Header::from_bytes("Content-Type", "text/plain; charset=utf-8").unwrap())
+		.boxed()
+}
+
+/// Parses the options of the waveform that was requested, and its input unless it is uploaded in the body of the request
+fn params(request: &Request, allowed_dirs: &[PathBuf]) -> Result<(FfAudio2JsonBuilder, OutputFormat), HttpError> {
+	let url = request.url();
+	let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+	if path != "/waveform" {
+		return Err(HttpError::new(404, "Not found, waveforms are generated at /waveform"));
+	}
+
+	let mut builder = FfAudio2JsonBuilder::default();
+	let mut format = OutputFormat::default();
+	let mut local_path = None;
+
+	for (key, value) in parse_query(query)? {
+		match key.as_str() {
+			"samples" => {
+				builder.samples(parse(&key, &value)?);
+			}
+			"channels" => {
+				let channels = value
+					.split([',', ' '])
+					.filter(|channel| !channel.is_empty())
+					.map(|channel| parse::<Channel>(&key, channel))
+					.collect::<Result<_, _>>()?;
+
+				builder.channels(channels);
+			}
+			"db_min" => {
+				builder.db_min(parse(&key, &value)?);
+			}
+			"db_max" => {
+				builder.db_max(parse(&key, &value)?);
+			}
+			"db_scale" => {
+				// A flag without a value is set, like on the command line
+				builder.db_scale(value.is_empty() || parse(&key, &value)?);
+			}
+			"format" => format = parse(&key, &value)?,
+			"path" => local_path = Some(PathBuf::from(value)),
+			_ => return Err(HttpError::new(400, format!("Unknown query parameter {key}"))),
+		}
+	}
+
+	match (request.method(), local_path) {
+		(Method::Get, Some(path)) => {
+			builder.input(local_input(&path, allowed_dirs)?);
+		}
+
+		(Method::Get, None) => {
+			return Err(HttpError::new(
+				400,
+				"Request a local audio file with the path parameter, or upload one in the body of a POST request",
+			))
+		}
+
+		// The input is the upload, which is received afterwards
+		(Method::Post, None) => {}
+
+		(Method::Post, Some(_)) => return Err(HttpError::new(400, "The path parameter can't be used with an upload")),
+
+		_ => return Err(HttpError::new(405, "Only GET and POST requests are supported")),
+	}
+
+	Ok((builder, format))
+}
+
+/// Generates the waveform into `output`, cancelling it once the deadline has passed
+fn generate(
+	mut builder: FfAudio2JsonBuilder,
+	format: OutputFormat,
+	output: &Path,
+	deadline: Instant,
+	generation: &Arc<Generation>,
+) -> Result<(), HttpError> {
+	builder
+		.output(Some(output.to_path_buf()))
+		.format(format)
+		.cancel(Some(generation.cancel.clone()));
+
+	// NDJSON is written in place so that its records can be streamed as they're generated, once decoding has started. The other formats
+	// fill in space that they reserved once they're complete, so they're only sent afterwards.
+	if format == OutputFormat::Ndjson {
+		let generation = generation.clone();
+		builder.stream_in_place(true).progress(move |_| generation.stream());
+	}
+
+	let config = builder.build().map_err(|err| HttpError::new(400, err.to_string()))?;
+
+	// The generator checks for cancellation as it decodes, which a timer sets once the deadline has passed
+	let (done, finished) = mpsc::channel::<()>();
+	std::thread::scope(|scope| {
+		scope.spawn(move || {
+			if finished.recv_timeout(deadline.saturating_duration_since(Instant::now())) == Err(RecvTimeoutError::Timeout) {
+				generation.cancel.store(true, Ordering::Relaxed);
+			}
+		});
+
+		let result = config.run();
+		drop(done);
+		result
+	})?;
+
+	Ok(())
+}
+
+/// Resolves a local audio file, which must be in one of the allowed directories
+fn local_input(path: &Path, allowed_dirs: &[PathBuf]) -> Result<PathBuf, HttpError> {
+	if allowed_dirs.is_empty() {
+		return Err(HttpError::new(403, "Local audio files can't be requested from this server"));
+	}
+
+	let path = path.canonicalize().map_err(|err| match err.kind() {
+		std::io::ErrorKind::NotFound => HttpError::new(404, format!("{}: {err}", path.display())),
+		_ => HttpError::new(403, format!("{}: {err}", path.display())),
+	})?;
+
+	if !allowed_dirs.iter().any(|dir| path.starts_with(dir)) {
+		return Err(HttpError::new(403, format!("{} is not in an allowed directory", path.display())));
+	}
+
+	if !path.is_file() {
+		return Err(HttpError::new(400, format!("{} is not a file", path.display())));
+	}
+
+	Ok(path)
+}
+
+/// Receives the body of the request into a new file in `temp_dir`
+fn receive_upload(request: &mut Request, temp_dir: &Path, max_size: u64, deadline: Instant) -> Result<TempPath, HttpError> {
+	let too_large = || HttpError::new(413, format!("Uploads must not be larger than {} MiB", max_size / 1024 / 1024));
+
+	if request.body_length().is_some_and(|length| length as u64 > max_size) {
+		return Err(too_large());
+	}
+
+	let mut file = tempfile::Builder::new().prefix("upload.").tempfile_in(temp_dir).map_err(Error::from)?;
+	let mut reader = request.as_reader().take(max_size + 1);
+	let mut buffer = vec![0; 64 * 1024];
+	let mut size = 0;
+
+	loop {
+		let read = reader.read(&mut buffer).map_err(|err| match err.kind() {
+			// The client sent nothing for longer than the idle timeout
+			ErrorKind::WouldBlock | ErrorKind::TimedOut => HttpError::new(408, "Timed out while receiving the upload"),
+			_ => HttpError::new(400, format!("Failed to receive upload: {err}")),
+		})?;
+
+		if read == 0 {
+			break;
+		}
+
+		size += read as u64;
+		if size > max_size {
+			return Err(too_large());
+		}
+
+		if Instant::now() >= deadline {
+			return Err(HttpError::new(408, "Timed out while receiving the upload"));
+		}
+
+		file.write_all(&buffer[..read]).map_err(Error::from)?;
+	}
+
+	if size == 0 {
+		return Err(HttpError::new(
+			400,
+			"Upload an audio file in the body of the request, or request a local one with the path parameter",
+		));
+	}
+
+	Ok(file.into_temp_path())
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, HttpError> {
+	value
+		.parse()
+		.map_err(|_| HttpError::new(400, format!("Invalid value for {key}: {value}")))
+}
+
+/// Splits a query string into its decoded keys and values
+fn parse_query(query: &str) -> Result<Vec<(String, String)>, HttpError> {
+	query
+		.split('&')
+		.filter(|pair| !pair.is_empty())
+		.map(|pair| {
+			let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+			Ok((percent_decode(key)?, percent_decode(value)?))
+		})
+		.collect()
+}
+
+fn percent_decode(s: &str) -> Result<String, HttpError> {
+	let invalid = || HttpError::new(400, format!("Invalid percent-encoding in query: {s}"));
+
+	let mut bytes = Vec::with_capacity(s.len());
+	let mut iter = s.bytes();
+
+	while let Some(byte) = iter.next() {
+		match byte {
+			b'+' => bytes.push(b' '),
+			b'%' => {
+				let hex = [iter.next().ok_or_else(invalid)?, iter.next().ok_or_else(invalid)?];
+				let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+				bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+			}
+			byte => bytes.push(byte),
+		}
+	}
+
+	String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[test]
+fn test_percent_decode() {
+	assert_eq!(percent_decode("a%20b+c").unwrap(), "a b c");
+	assert_eq!(percent_decode("%2Fmusic%2fsong.wav").unwrap(), "/music/song.wav");
+	assert_eq!(percent_decode("%E2%9C%93").unwrap(), "✓");
+
+	for invalid in ["%2", "%zz", "%ff"] {
+		assert_eq!(percent_decode(invalid).unwrap_err().status, 400, "{invalid}");
+	}
+}
+
+#[test]
+fn test_parse_query() {
+	assert!(parse_query("").unwrap().is_empty());
+
+	assert_eq!(
+		parse_query("samples=100&&channels=left%2Cright&db_scale").unwrap(),
+		[
+			("samples".to_string(), "100".to_string()),
+			("channels".to_string(), "left,right".to_string()),
+			("db_scale".to_string(), String::new()),
+		]
+	);
+
+	assert_eq!(parse_query("path=%zz").unwrap_err().status, 400);
+}
+
+#[test]
+fn test_http_error() {
+	assert_eq!(HttpError::from(Error::Cancelled).status, 503);
+	assert_eq!(HttpError::from(Error::Io(std::io::Error::other("failed"))).status, 500);
+	assert_eq!(HttpError::from(Error::OutputExists(PathBuf::from("output.json"))).status, 500);
+}
+
+#[test]
+fn test_local_input() {
+	let root = tempfile::tempdir().unwrap();
+	let allowed = root.path().join("allowed");
+	std::fs::create_dir_all(allowed.join("album")).unwrap();
+	std::fs::write(allowed.join("song.wav"), "").unwrap();
+	std::fs::write(root.path().join("secret.wav"), "").unwrap();
+
+	let allowed_dirs = [allowed.canonicalize().unwrap()];
+	let status = |path: &Path| local_input(path, &allowed_dirs).unwrap_err().status;
+
+	assert_eq!(
+		local_input(&allowed.join("album/../song.wav"), &allowed_dirs).unwrap(),
+		allowed_dirs[0].join("song.wav")
+	);
+
+	assert_eq!(local_input(&allowed.join("song.wav"), &[]).unwrap_err().status, 403);
+	assert_eq!(status(&allowed.join("missing.wav")), 404);
+	assert_eq!(status(&allowed.join("../secret.wav")), 403);
+	assert_eq!(status(&root.path().join("secret.wav")), 403);
+	assert_eq!(status(&allowed.join("album")), 400);
+
+	#[cfg(unix)]
+	{
+		std::os::unix::fs::symlink(root.path().join("secret.wav"), allowed.join("link.wav")).unwrap();
+		assert_eq!(status(&allowed.join("link.wav")), 403);
+	}
+}
+
+#[test]
+fn test_params() {
+	let request = |method: Method, path: &str| -> Request { tiny_http::TestRequest::new().with_method(method).with_path(path).into() };
+	let status = |method: Method, path: &str| params(&request(method, path), &[]).err().unwrap().status;
+
+	let (_, format) = params(&request(Method::Post, "/waveform?samples=100&channels=left,right&format=dat"), &[]).unwrap();
+	assert_eq!(format, OutputFormat::Dat);
+
+	let (_, format) = params(&request(Method::Post, "/waveform"), &[]).unwrap();
+	assert_eq!(format, OutputFormat::Json);
+
+	assert_eq!(status(Method::Post, "/"), 404);
+	assert_eq!(status(Method::Post, "/waveform?samples=many"), 400);
+	assert_eq!(status(Method::Post, "/waveform?channels=left,nowhere"), 400);
+	assert_eq!(status(Method::Post, "/waveform?unknown=1"), 400);
+	assert_eq!(status(Method::Post, "/waveform?path=song.wav"), 400);
+	assert_eq!(status(Method::Get, "/waveform"), 400);
+	assert_eq!(status(Method::Get, "/waveform?path=song.wav"), 403);
+	assert_eq!(status(Method::Put, "/waveform"), 405);
+}
+
+#[test]
+fn test_try_queue() {
+	let queued = AtomicUsize::new(0);
+
+	assert!(try_queue(&queued, 2));
+	assert!(try_queue(&queued, 2));
+	assert!(!try_queue(&queued, 2));
+	assert_eq!(queued.load(Ordering::Relaxed), 2);
+
+	// A worker taking a job from the queue makes room for another
+	queued.fetch_sub(1, Ordering::Relaxed);
+	assert!(try_queue(&queued, 2));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_private_temp_dir() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let temp_dir = private_temp_dir().unwrap();
+	assert_eq!(temp_dir.path().metadata().unwrap().permissions().mode() & 0o777, 0o700);
+}
+
+#[test]
+fn test_receive_upload() {
+	let temp_dir = tempfile::tempdir().unwrap();
+	let later = Instant::now() + Duration::from_secs(60);
+
+	let request = |body: &'static str| -> Request {
+		tiny_http::TestRequest::new()
+			.with_method(Method::Post)
+			.with_path("/waveform")
+			.with_body(body)
+			.into()
+	};
+
+	let upload = receive_upload(&mut request("audio"), temp_dir.path(), 5, later).unwrap();
+	assert!(upload.starts_with(temp_dir.path()));
+	assert_eq!(std::fs::read_to_string(&upload).unwrap(), "audio");
+
+	let path = upload.to_path_buf();
+	drop(upload);
+	assert!(!path.exists());
+
+	assert_eq!(receive_upload(&mut request("audio!"), temp_dir.path(), 5, later).unwrap_err().status, 413);
+	assert_eq!(receive_upload(&mut request(""), temp_dir.path(), 5, later).unwrap_err().status, 400);
+	assert_eq!(
+		receive_upload(&mut request("audio"), temp_dir.path(), 5, Instant::now())
+			.unwrap_err()
+			.status,
+		408
+	);
+
+	// Uploads that fail aren't left behind
+	assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_tail() {
+	let temp_dir = tempfile::tempdir().unwrap();
+	let path = temp_dir.path().join("output.ndjson");
+	std::fs::write(&path, "header\n").unwrap();
+
+	let generation = Arc::new(Generation::default());
+	generation.stream();
+
+	let mut tail = Tail {
+		file: File::open(&path).unwrap(),
+		generation: generation.clone(),
+	};
+
+	// What's written while the output is being read is read too, until generation has finished
+	let writer = std::thread::spawn({
+		let path = path.clone();
+		let generation = generation.clone();
+
+		move || {
+			std::thread::sleep(POLL_INTERVAL * 2);
+			std::fs::OpenOptions::new()
+				.append(true)
+				.open(&path)
+				.unwrap()
+				.write_all(b"points\n")
+				.unwrap();
+			generation.finish(Ok(()));
+		}
+	});
+
+	let mut output = String::new();
+	tail.read_to_string(&mut output).unwrap();
+	writer.join().unwrap();
+	assert_eq!(output, "header\npoints\n");
+
+	// Outputs that fail partway through are cut short with an error
+	let generation = Arc::new(Generation::default());
+	generation.finish(Err(HttpError::from(Error::Cancelled)));
+
+	let mut tail = Tail {
+		file: File::open(&path).unwrap(),
+		generation,
+	};
+	assert!(tail.read_to_string(&mut String::new()).is_err());
+}
+
+#[test]
+fn test_idle_upload() {
+	let listener = listen("127.0.0.1:0", Duration::from_millis(100)).unwrap();
+	let address = listener.local_addr().unwrap();
+	let server = Server::from_listener(listener, None).unwrap();
+
+	// The body is larger than what the server reads before handing over the request, and the client stops sending partway through it
+	let mut client = TcpStream::connect(address).unwrap();
+	client
+		.write_all(b"POST /waveform HTTP/1.1\r\nHost: localhost\r\nContent-Length: 65536\r\n\r\n")
+		.unwrap();
+	client.write_all(&[0; 4096]).unwrap();
+
+	let mut request = server.recv().unwrap();
+	let temp_dir = tempfile::tempdir().unwrap();
+
+	let start = Instant::now();
+	let err = receive_upload(&mut request, temp_dir.path(), 1024 * 1024, start + Duration::from_secs(60)).unwrap_err();
+	assert_eq!(err.status, 408);
+	assert!(start.elapsed() < Duration::from_secs(10));
+}
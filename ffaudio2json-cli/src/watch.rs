@@ -0,0 +1,385 @@
+use crate::{
+	batch::{self, Input},
+	options::WatchOptions,
+};
+use notify_debouncer_full::{new_debouncer, notify::RecursiveMode};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::{Path, PathBuf},
+	process::ExitCode,
+	sync::mpsc,
+	time::Duration,
+};
+
+/// A file or directory argument that is watched for changes
+struct Root {
+	/// The absolute path, as the paths of events are absolute
+	path: PathBuf,
+	is_dir: bool,
+}
+
+/// What a path that changed is now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	Dir,
+	File,
+	Missing,
+}
+impl Kind {
+	fn of(path: &Path) -> Self {
+		if path.is_dir() {
+			Kind::Dir
+		} else if path.is_file() {
+			Kind::File
+		} else {
+			Kind::Missing
+		}
+	}
+}
+
+/// What to do about the paths that changed
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Actions {
+	/// The inputs to generate waveforms for, by path, with their relative directories
+	changed: BTreeMap<PathBuf, PathBuf>,
+
+	/// The known inputs that were removed, whose outputs are removed too
+	removed: BTreeSet<PathBuf>,
+}
+
+/// Decides what to do about the `paths` that changed, given what `kind` says each of them is now
+///
+/// `search_dir` finds the inputs in a directory that was created or moved into a root, with their relative directories starting at the given one.
+fn actions(
+	roots: &[Root],
+	known: &BTreeSet<PathBuf>,
+	paths: impl IntoIterator<Item = PathBuf>,
+	kind: impl Fn(&Path) -> Kind,
+	mut search_dir: impl FnMut(&Path, &Path) -> Vec<Input>,
+) -> Actions {
+	let mut actions = Actions::default();
+
+	for path in paths {
+		let kind = kind(&path);
+
+		if kind == Kind::Missing {
+			// The path of a removed directory covers all of the inputs that were in it
+			actions.removed.extend(known.iter().filter(|input| input.starts_with(&path)).cloned());
+			continue;
+		}
+
+		// The directories of file roots are watched, so their other files are seen too, including the outputs written next to them
+		let Some((root, relative_path)) = roots.iter().find_map(|root| {
+			let relative_path = if root.is_dir {
+				path.strip_prefix(&root.path).ok()?
+			} else {
+				(path == root.path).then_some(Path::new(""))?
+			};
+			Some((root, relative_path))
+		}) else {
+			continue;
+		};
+
+		// Outputs and their temporary files are written next to the inputs by default, which must not trigger regeneration
+		if relative_path.iter().any(batch::is_hidden) {
+			continue;
+		}
+
+		match kind {
+			// A directory that was created or moved here. Inputs that were already in it have events of their own if they changed.
+			Kind::Dir if root.is_dir => actions.changed.extend(
+				search_dir(&path, relative_path)
+					.into_iter()
+					.filter(|input| !known.contains(&input.path))
+					.map(|input| (input.path, input.relative_dir)),
+			),
+
+			Kind::File if !root.is_dir || batch::is_audio_file(&path) => {
+				let relative_dir = relative_path.parent().unwrap_or(Path::new("")).to_path_buf();
+				actions.changed.insert(path, relative_dir);
+			}
+
+			_ => {}
+		}
+	}
+
+	actions
+}
+
+/// Generates waveforms for the inputs, then regenerates them whenever they change until the process is killed
+///
+/// New audio files in directory inputs are picked up, and the outputs of inputs that are removed are removed too.
+pub fn watch(watch: &WatchOptions) -> ExitCode {
+	let opt = &watch.options;
+
+	if opt.output.is_some() {
+		eprintln!("--output can't be used with watch, use --output-dir and --output-template instead");
+		return ExitCode::from(2);
+	}
+
+	#[cfg(feature = "render")]
+	if opt.render.render.is_some() {
+		eprintln!("--render can't be used with watch");
+		return ExitCode::from(2);
+	}
+
+	let mut roots = Vec::with_capacity(opt.inputs.len());
+	for path in &opt.inputs {
+		match std::path::absolute(path).and_then(|path| {
+			Ok(Root {
+				is_dir: path.metadata()?.is_dir(),
+				path,
+			})
+		}) {
+			Ok(root) => roots.push(root),
+			Err(err) => {
+				eprintln!("{}: {err}", path.display());
+				return ExitCode::FAILURE;
+			}
+		}
+	}
+
+	// Start watching before the initial generation, so that changes made while it's running aren't missed
+	let (tx, rx) = mpsc::channel();
+	let mut debouncer = match new_debouncer(Duration::from_millis(watch.debounce), None, tx) {
+		Ok(debouncer) => debouncer,
+		Err(err) => {
+			eprintln!("Failed to watch for changes: {err}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	// Editors often replace files instead of writing to them, which a watch on the file itself doesn't survive, so the directories of files are watched instead
+	let mut watched = BTreeMap::new();
+	for root in &roots {
+		if root.is_dir {
+			watched.insert(root.path.as_path(), RecursiveMode::Recursive);
+		} else if let Some(parent) = root.path.parent() {
+			watched.entry(parent).or_insert(RecursiveMode::NonRecursive);
+		}
+	}
+
+	for (path, mode) in watched {
+		if let Err(err) = debouncer.watch(path, mode) {
+			eprintln!("{}: Failed to watch for changes: {err}", path.display());
+			return ExitCode::FAILURE;
+		}
+	}
+
+	let mut inputs = Vec::new();
+	for root in &roots {
+		if !root.is_dir {
+			inputs.push(Input {
+				path: root.path.clone(),
+				relative_dir: PathBuf::new(),
+			});
+		} else if let Err(err) = batch::search_dir(&root.path, Path::new(""), &mut inputs) {
+			eprintln!("{}: {err}", root.path.display());
+		}
+	}
+
+	// The inputs that have been seen, so that the outputs of a removed directory can be found
+	let mut known = inputs.iter().map(|input| input.path.clone()).collect::<BTreeSet<_>>();
+
+	if let Err(err) = crate::generate(opt, &inputs) {
+		eprintln!("{err}");
+		return ExitCode::from(2);
+	}
+
+	if !opt.quiet {
+		eprintln!("Watching for changes...");
+	}
+
+	for events in rx {
+		let events = match events {
+			Ok(events) => events,
+			Err(errors) => {
+				for err in errors {
+					eprintln!("Failed to watch for changes: {err}");
+				}
+				continue;
+			}
+		};
+
+		// Inputs are opened and read to generate their waveforms, which must not trigger regeneration
+		let paths = events
+			.into_iter()
+			.filter(|event| !event.kind.is_access())
+			.flat_map(|event| event.event.paths)
+			.collect::<BTreeSet<_>>();
+
+		let Actions { changed, removed } = actions(&roots, &known, paths, Kind::of, |dir, relative_dir| {
+			let mut found = Vec::new();
+			if let Err(err) = batch::search_dir(dir, relative_dir, &mut found) {
+				eprintln!("{}: {err}", dir.display());
+			}
+			found
+		});
+
+		for path in removed {
+			known.remove(&path);
+
+			let Some(input) = roots.iter().find_map(|root| {
+				Some(Input {
+					relative_dir: path.strip_prefix(&root.path).ok()?.parent().unwrap_or(Path::new("")).to_path_buf(),
+					path: path.clone(),
+				})
+			}) else {
+				continue;
+			};
+
+			let result = opt
+				.builder(&input)
+				.map_err(Into::into)
+				.and_then(|builder| Ok(builder.build()?.remove_output()?));
+
+			match result {
+				Ok(Some(output)) if !opt.quiet => eprintln!("Removed {}", output.display()),
+				Ok(_) => {}
+				Err::<_, Box<dyn std::error::Error>>(err) => eprintln!("{}: {err}", path.display()),
+			}
+		}
+
+		if !changed.is_empty() {
+			let inputs = changed
+				.into_iter()
+				.map(|(path, relative_dir)| Input { path, relative_dir })
+				.collect::<Vec<_>>();
+
+			known.extend(inputs.iter().map(|input| input.path.clone()));
+
+			// The options were already used for the initial generation, so they're valid
+			if let Err(err) = crate::generate(opt, &inputs) {
+				eprintln!("{err}");
+			}
+		}
+	}
+
+	ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+fn test_roots() -> Vec<Root> {
+	vec![
+		Root {
+			path: PathBuf::from("/music"),
+			is_dir: true,
+		},
+		Root {
+			path: PathBuf::from("/podcasts/episode.mp3"),
+			is_dir: false,
+		},
+	]
+}
+
+#[cfg(test)]
+fn test_actions(known: &[&str], paths: &[(&str, Kind)], found: &[(&str, &str)]) -> Actions {
+	let kinds = paths.iter().map(|&(path, kind)| (PathBuf::from(path), kind)).collect::<BTreeMap<_, _>>();
+
+	actions(
+		&test_roots(),
+		&known.iter().map(PathBuf::from).collect(),
+		kinds.keys().cloned(),
+		|path| kinds[path],
+		|_, _| {
+			found
+				.iter()
+				.map(|&(path, relative_dir)| Input {
+					path: path.into(),
+					relative_dir: relative_dir.into(),
+				})
+				.collect()
+		},
+	)
+}
+
+#[cfg(test)]
+fn changed(changed: &[(&str, &str)]) -> BTreeMap<PathBuf, PathBuf> {
+	changed.iter().map(|&(path, relative_dir)| (path.into(), relative_dir.into())).collect()
+}
+
+#[test]
+fn test_actions_changed() {
+	let actions = test_actions(
+		&[],
+		&[
+			("/music/a.wav", Kind::File),
+			("/music/album/b.flac", Kind::File),
+			("/music/notes.txt", Kind::File),
+			("/elsewhere/c.wav", Kind::File),
+		],
+		&[],
+	);
+
+	assert_eq!(actions.changed, changed(&[("/music/a.wav", ""), ("/music/album/b.flac", "album")]));
+	assert!(actions.removed.is_empty());
+}
+
+#[test]
+fn test_actions_hidden() {
+	let actions = test_actions(
+		&[],
+		&[
+			("/music/.a.wav.tmp", Kind::File),
+			("/music/.hidden/b.wav", Kind::File),
+			("/music/.hidden", Kind::Dir),
+		],
+		&[("/music/.hidden/b.wav", ".hidden")],
+	);
+
+	assert_eq!(actions, Actions::default());
+}
+
+#[test]
+fn test_actions_new_dir() {
+	// Only the inputs in the directory that aren't known yet are generated
+	let actions = test_actions(
+		&["/music/album/a.wav"],
+		&[("/music/album", Kind::Dir)],
+		&[("/music/album/a.wav", "album"), ("/music/album/b.wav", "album")],
+	);
+
+	assert_eq!(actions.changed, changed(&[("/music/album/b.wav", "album")]));
+
+	// Directories next to file roots aren't searched
+	let actions = test_actions(&[], &[("/podcasts/season", Kind::Dir)], &[("/podcasts/season/a.mp3", "season")]);
+	assert_eq!(actions, Actions::default());
+}
+
+#[test]
+fn test_actions_removed() {
+	let known = ["/music/album/a.wav", "/music/album/b.wav", "/music/albums.wav", "/podcasts/episode.mp3"];
+
+	let actions = test_actions(&known, &[("/music/album", Kind::Missing)], &[]);
+	assert_eq!(
+		actions.removed,
+		BTreeSet::from([PathBuf::from("/music/album/a.wav"), PathBuf::from("/music/album/b.wav")])
+	);
+	assert!(actions.changed.is_empty());
+
+	let actions = test_actions(&known, &[("/podcasts/episode.mp3", Kind::Missing)], &[]);
+	assert_eq!(actions.removed, BTreeSet::from([PathBuf::from("/podcasts/episode.mp3")]));
+
+	// Files that weren't inputs have nothing to remove
+	let actions = test_actions(&known, &[("/music/.a.wav.json.tmp", Kind::Missing)], &[]);
+	assert_eq!(actions, Actions::default());
+}
+
+#[test]
+fn test_actions_outputs() {
+	// Outputs written next to their inputs don't trigger regeneration, including next to file roots whose directories are watched
+	let actions = test_actions(
+		&["/music/a.wav", "/podcasts/episode.mp3"],
+		&[
+			("/music/a.wav.json", Kind::File),
+			("/podcasts/episode.mp3.json", Kind::File),
+			("/podcasts/other.mp3", Kind::File),
+		],
+		&[],
+	);
+	assert_eq!(actions, Actions::default());
+
+	// A file root that was replaced by an editor is still an input
+	let actions = test_actions(&["/podcasts/episode.mp3"], &[("/podcasts/episode.mp3", Kind::File)], &[]);
+	assert_eq!(actions.changed, changed(&[("/podcasts/episode.mp3", "")]));
+}
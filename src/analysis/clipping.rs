@@ -0,0 +1,156 @@
+use super::{Buckets, Interval};
+use crate::{
+	audio::{FrameView, PlanarSample},
+	output::encoder::Encoder,
+	FfAudio2Json,
+};
+use std::io::Write;
+
+#[derive(Default)]
+struct ClippingRun {
+	/// The position at which the current run of full scale samples started, and its length
+	run: Option<(u64, u64)>,
+	regions: Vec<Interval>,
+	samples: u64,
+}
+
+pub(crate) struct ClippingDetector {
+	epsilon: f64,
+
+	/// Minimum number of consecutive full scale samples for a run to be reported as clipping
+	min_samples: u64,
+
+	sample_rate: f64,
+	channels: Vec<ClippingRun>,
+
+	/// Whether the clipped regions are reported
+	report: bool,
+
+	/// Number of clipped samples in each point of the waveform, if requested
+	points: Option<(Buckets, Vec<u32>)>,
+}
+impl ClippingDetector {
+	pub fn new(config: &FfAudio2Json, sample_rate: u32, buckets: Buckets) -> Self {
+		Self {
+			epsilon: config.clipping_epsilon,
+			min_samples: (config.clipping_min_samples as u64).max(1),
+			sample_rate: sample_rate as f64,
+			channels: Vec::new(),
+			report: config.detect_clipping,
+			points: config.clipped_channel.then(|| (buckets, Vec::new())),
+		}
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView, position: u64) {
+		if self.channels.len() < frame.channels() {
+			self.channels.resize_with(frame.channels(), Default::default);
+		}
+
+		for sample in 0..frame.samples() {
+			let position = position + sample as u64;
+
+			for channel in 0..frame.channels() {
+				if frame.sample(sample, channel).is_full_scale(self.epsilon) {
+					let (start, len) = self.channels[channel].run.get_or_insert((position, 0));
+					*len += 1;
+
+					let (start, len) = (*start, *len);
+					if len == self.min_samples {
+						// The run just became long enough to count, so attribute all of it
+						self.count_points(start..=position);
+					} else if len > self.min_samples {
+						self.count_points(position..=position);
+					}
+				} else {
+					self.end_run(channel, position);
+				}
+			}
+		}
+	}
+
+	fn count_points(&mut self, positions: std::ops::RangeInclusive<u64>) {
+		if let Some((ref buckets, ref mut counts)) = self.points {
+			for position in positions {
+				if let Some(index) = buckets.index(position) {
+					if counts.len() <= index {
+						counts.resize(index + 1, 0);
+					}
+					counts[index] += 1;
+				}
+			}
+		}
+	}
+
+	fn end_run(&mut self, channel: usize, position: u64) {
+		let channel = &mut self.channels[channel];
+
+		if let Some((start, len)) = channel.run.take() {
+			if len >= self.min_samples {
+				channel.regions.push(Interval {
+					start: start as f64 / self.sample_rate,
+					end: position as f64 / self.sample_rate,
+				});
+				channel.samples += len;
+			}
+		}
+	}
+
+	pub fn finish(&mut self, position: u64, points: usize) {
+		for channel in 0..self.channels.len() {
+			self.end_run(channel, position);
+		}
+
+		if let Some((_, ref mut counts)) = self.points {
+			counts.resize(points, 0);
+		}
+	}
+
+	/// The total number of clipped regions across all channels
+	pub fn count(&self) -> usize {
+		self.channels.iter().map(|channel| channel.regions.len()).sum()
+	}
+
+	pub fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		encoder.map(2)?;
+		encoder.key("count")?;
+		encoder.u64(self.count() as u64)?;
+		encoder.key("channels")?;
+		encoder.array(self.channels.len())?;
+
+		for channel in &self.channels {
+			encoder.map(3)?;
+			encoder.key("count")?;
+			encoder.u64(channel.regions.len() as u64)?;
+			encoder.key("samples")?;
+			encoder.u64(channel.samples)?;
+			encoder.key("regions")?;
+			encoder.array(channel.regions.len())?;
+			for region in &channel.regions {
+				region.write(encoder)?;
+			}
+			encoder.end()?;
+			encoder.end()?;
+		}
+
+		encoder.end()?;
+		encoder.end()
+	}
+
+	pub fn write_points(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		let counts = self.points.as_ref().map(|(_, counts)| counts.as_slice()).unwrap_or_default();
+
+		encoder.array(counts.len())?;
+		for count in counts {
+			encoder.u64(*count as u64)?;
+		}
+		encoder.end()
+	}
+
+	pub fn has_report(&self) -> bool {
+		self.report
+	}
+
+	pub fn has_points(&self) -> bool {
+		self.points.is_some()
+	}
+}
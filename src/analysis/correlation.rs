@@ -0,0 +1,88 @@
+use super::Buckets;
+use crate::{
+	audio::{FrameView, PlanarSample},
+	output::encoder::Encoder,
+};
+use std::io::Write;
+
+#[derive(Default, Clone, Copy)]
+struct Sums {
+	left_right: f64,
+	left_left: f64,
+	right_right: f64,
+}
+impl Sums {
+	#[inline]
+	fn push(&mut self, left: f64, right: f64) {
+		self.left_right += left * right;
+		self.left_left += left * left;
+		self.right_right += right * right;
+	}
+
+	/// The correlation coefficient in the range of -1.0 to 1.0, or 0.0 if either channel is silent
+	fn coefficient(&self) -> f64 {
+		let denominator = (self.left_left * self.right_right).sqrt();
+		if denominator > 0.0 {
+			(self.left_right / denominator).clamp(-1.0, 1.0)
+		} else {
+			0.0
+		}
+	}
+}
+
+/// Measures the phase correlation between the left and right channels, for each point of the waveform and for the whole stream
+pub(crate) struct CorrelationMeter {
+	buckets: Buckets,
+	precision: usize,
+
+	total: Sums,
+
+	/// The sums of each point of the waveform
+	points: Vec<Sums>,
+}
+impl CorrelationMeter {
+	pub fn new(buckets: Buckets, precision: usize) -> Self {
+		Self {
+			buckets,
+			precision,
+			total: Sums::default(),
+			points: Vec::new(),
+		}
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView, position: u64) {
+		// A mono stream is correlated with itself
+		let right = (frame.channels() > 1) as usize;
+
+		for sample in 0..frame.samples() {
+			let left = frame.sample(sample, 0).amplitude();
+			let right = frame.sample(sample, right).amplitude();
+
+			self.total.push(left, right);
+
+			if let Some(index) = self.buckets.index(position + sample as u64) {
+				if self.points.len() <= index {
+					self.points.resize(index + 1, Sums::default());
+				}
+				self.points[index].push(left, right);
+			}
+		}
+	}
+
+	pub fn finish(&mut self, points: usize) {
+		self.points.resize(points, Sums::default());
+	}
+
+	/// The correlation coefficient of the whole stream
+	pub fn total(&self) -> f64 {
+		self.total.coefficient()
+	}
+
+	pub fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		encoder.array(self.points.len())?;
+		for point in &self.points {
+			encoder.decimal(point.coefficient(), self.precision)?;
+		}
+		encoder.end()
+	}
+}
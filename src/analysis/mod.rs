@@ -0,0 +1,219 @@
+//! Analysis of the full-resolution decoded samples, which runs alongside the waveform generation
+
+use crate::{audio::FrameView, output::encoder::Encoder, FfAudio2Json};
+use std::{io::Write, ops::Range};
+
+mod correlation;
+pub(crate) use correlation::CorrelationMeter;
+
+mod clipping;
+pub(crate) use clipping::ClippingDetector;
+
+mod stats;
+pub use stats::ChannelStats;
+pub(crate) use stats::StatsCollector;
+
+mod silence;
+pub(crate) use silence::SilenceDetector;
+
+mod trim;
+pub(crate) use trim::TrimDetector;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An interval of the audio stream, in seconds
+pub(crate) struct Interval {
+	/// The start of the interval, in seconds
+	pub start: f64,
+
+	/// The end of the interval, in seconds
+	pub end: f64,
+}
+impl Interval {
+	pub(crate) fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		encoder.map(2)?;
+		encoder.key("start")?;
+		encoder.f64(self.start)?;
+		encoder.key("end")?;
+		encoder.f64(self.end)?;
+		encoder.end()
+	}
+}
+
+#[derive(Debug, Clone)]
+/// Maps sample positions to the point of the waveform that they contribute to
+pub(crate) struct Buckets {
+	/// The range of samples (per channel) that points are generated from
+	pub window: Range<u64>,
+
+	/// The number of samples (per channel) in each point
+	pub capacity: u64,
+
+	/// The maximum number of points
+	pub points: u64,
+}
+impl Buckets {
+	pub fn index(&self, position: u64) -> Option<usize> {
+		if !self.window.contains(&position) {
+			return None;
+		}
+
+		let index = (position - self.window.start) / self.capacity.max(1);
+		(index < self.points).then_some(index as usize)
+	}
+}
+
+pub(crate) struct Analysis {
+	/// The number of samples (per channel) that have been analysed so far
+	position: u64,
+	pub(crate) silence: Option<SilenceDetector>,
+	pub(crate) trim: Option<TrimDetector>,
+	pub(crate) clipping: Option<ClippingDetector>,
+	pub(crate) stats: Option<StatsCollector>,
+	pub(crate) correlation: Option<CorrelationMeter>,
+}
+impl Analysis {
+	pub fn new(config: &FfAudio2Json, sample_rate: u32, buckets: Buckets) -> Self {
+		Self {
+			position: 0,
+			silence: config.detect_silence.then(|| SilenceDetector::new(config, sample_rate)),
+			trim: None,
+			clipping: (config.detect_clipping || config.clipped_channel).then(|| ClippingDetector::new(config, sample_rate, buckets.clone())),
+			stats: config.stats.then(|| StatsCollector::new(sample_rate)),
+			correlation: config.correlation.then(|| CorrelationMeter::new(buckets, config.precision)),
+		}
+	}
+
+	/// Analysis for the first pass over the stream, which finds the leading and trailing silence to trim
+	pub fn for_trim(config: &FfAudio2Json, sample_rate: u32) -> Self {
+		Self {
+			position: 0,
+			silence: None,
+			trim: Some(TrimDetector::new(config, sample_rate)),
+			clipping: None,
+			stats: None,
+			correlation: None,
+		}
+	}
+
+	/// No analysis at all, for decoding segments of the stream in parallel
+	pub fn inactive() -> Self {
+		Self {
+			position: 0,
+			silence: None,
+			trim: None,
+			clipping: None,
+			stats: None,
+			correlation: None,
+		}
+	}
+
+	/// The number of samples (per channel) that have been analysed
+	pub fn position(&self) -> u64 {
+		self.position
+	}
+
+	/// Whether any analysis is enabled, in which case the entire stream must be decoded
+	pub fn is_active(&self) -> bool {
+		self.silence.is_some() || self.trim.is_some() || self.clipping.is_some() || self.stats.is_some() || self.correlation.is_some()
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView) {
+		if let Some(ref mut silence) = self.silence {
+			silence.feed(frame, self.position);
+		}
+
+		if let Some(ref mut trim) = self.trim {
+			trim.feed(frame, self.position);
+		}
+
+		if let Some(ref mut clipping) = self.clipping {
+			clipping.feed(frame, self.position);
+		}
+
+		if let Some(ref mut stats) = self.stats {
+			stats.feed(frame);
+		}
+
+		if let Some(ref mut correlation) = self.correlation {
+			correlation.feed(frame, self.position);
+		}
+
+		self.position += frame.samples() as u64;
+	}
+
+	/// Finishes the analysis, given the number of points that were written to the waveform
+	pub fn finish(&mut self, points: usize) {
+		if let Some(ref mut silence) = self.silence {
+			silence.finish(self.position);
+		}
+
+		if let Some(ref mut clipping) = self.clipping {
+			clipping.finish(self.position, points);
+		}
+
+		if let Some(ref mut correlation) = self.correlation {
+			correlation.finish(points);
+		}
+	}
+
+	/// The number of keys that [`Analysis::write`] adds to the output
+	pub fn keys(&self) -> usize {
+		let clipping = self
+			.clipping
+			.as_ref()
+			.map(|clipping| clipping.has_report() as usize + clipping.has_points() as usize)
+			.unwrap_or(0);
+
+		self.stats.is_some() as usize + self.correlation.is_some() as usize * 2 + self.silence.is_some() as usize + clipping
+	}
+
+	pub fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		if let Some(ref stats) = self.stats {
+			encoder.key("stats")?;
+			stats.write(encoder)?;
+		}
+
+		if let Some(ref correlation) = self.correlation {
+			encoder.key("correlation")?;
+			correlation.write(encoder)?;
+			encoder.key("correlation_total")?;
+			encoder.f64(correlation.total())?;
+		}
+
+		if let Some(ref silence) = self.silence {
+			encoder.key("silence")?;
+			silence.write(encoder)?;
+		}
+
+		if let Some(ref clipping) = self.clipping {
+			if clipping.has_report() {
+				encoder.key("clipping")?;
+				clipping.write(encoder)?;
+			}
+
+			if clipping.has_points() {
+				encoder.key("clipped")?;
+				clipping.write_points(encoder)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[test]
+fn test_buckets_index() {
+	let buckets = Buckets {
+		window: 10..40,
+		capacity: 10,
+		points: 2,
+	};
+
+	assert_eq!(buckets.index(9), None);
+	assert_eq!(buckets.index(10), Some(0));
+	assert_eq!(buckets.index(19), Some(0));
+	assert_eq!(buckets.index(20), Some(1));
+	assert_eq!(buckets.index(29), Some(1));
+	assert_eq!(buckets.index(30), None);
+	assert_eq!(buckets.index(40), None);
+}
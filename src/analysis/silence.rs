@@ -0,0 +1,102 @@
+use super::Interval;
+use crate::{
+	audio::{FrameView, PlanarSample},
+	output::encoder::Encoder,
+	util, FfAudio2Json,
+};
+use std::io::Write;
+
+#[derive(Default)]
+struct SilenceRun {
+	/// The position at which the current silent run started, if we are in one
+	start: Option<u64>,
+	intervals: Vec<Interval>,
+}
+
+pub(crate) struct SilenceDetector {
+	/// Linear amplitude below which a sample is considered silent
+	threshold: f64,
+
+	/// Minimum number of consecutive silent samples for a run to be reported
+	min_samples: u64,
+
+	sample_rate: f64,
+	per_channel: bool,
+
+	/// One run per channel if `per_channel` is set, otherwise a single run for all channels
+	runs: Vec<SilenceRun>,
+}
+impl SilenceDetector {
+	pub fn new(config: &FfAudio2Json, sample_rate: u32) -> Self {
+		Self {
+			threshold: util::from_db(config.silence_threshold),
+			min_samples: (config.silence_min_duration.max(0.0) * sample_rate as f64).ceil() as u64,
+			sample_rate: sample_rate as f64,
+			per_channel: config.silence_per_channel,
+			runs: Vec::new(),
+		}
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView, position: u64) {
+		let runs = if self.per_channel { frame.channels() } else { 1 };
+		if self.runs.len() < runs {
+			self.runs.resize_with(runs, Default::default);
+		}
+
+		for sample in 0..frame.samples() {
+			let position = position + sample as u64;
+
+			if self.per_channel {
+				for channel in 0..frame.channels() {
+					let silent = frame.sample(sample, channel).amplitude().abs() < self.threshold;
+					self.update(channel, position, silent);
+				}
+			} else {
+				let silent = (0..frame.channels()).all(|channel| frame.sample(sample, channel).amplitude().abs() < self.threshold);
+				self.update(0, position, silent);
+			}
+		}
+	}
+
+	fn update(&mut self, run: usize, position: u64, silent: bool) {
+		let run = &mut self.runs[run];
+
+		if silent {
+			run.start.get_or_insert(position);
+		} else if let Some(start) = run.start.take() {
+			if position - start >= self.min_samples {
+				run.intervals.push(Interval {
+					start: start as f64 / self.sample_rate,
+					end: position as f64 / self.sample_rate,
+				});
+			}
+		}
+	}
+
+	/// Closes any silent runs that extend to the end of the stream
+	pub fn finish(&mut self, position: u64) {
+		for run in 0..self.runs.len() {
+			self.update(run, position, false);
+		}
+	}
+
+	pub fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		fn write_intervals(encoder: &mut Encoder<impl Write>, intervals: &[Interval]) -> Result<(), std::io::Error> {
+			encoder.array(intervals.len())?;
+			for interval in intervals {
+				interval.write(encoder)?;
+			}
+			encoder.end()
+		}
+
+		if self.per_channel {
+			encoder.array(self.runs.len())?;
+			for run in &self.runs {
+				write_intervals(encoder, &run.intervals)?;
+			}
+			encoder.end()
+		} else {
+			write_intervals(encoder, self.runs.first().map(|run| run.intervals.as_slice()).unwrap_or_default())
+		}
+	}
+}
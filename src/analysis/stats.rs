@@ -0,0 +1,153 @@
+use crate::{
+	audio::{FrameView, PlanarSample},
+	output::encoder::Encoder,
+	util,
+};
+use std::io::Write;
+
+/// Length in seconds of the windows that the noise floor is measured over
+const NOISE_FLOOR_WINDOW: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+/// Statistics of a single channel of the audio stream
+///
+/// Levels are in dBFS, and may be negative infinity for digital silence.
+pub struct ChannelStats {
+	/// Peak level in dBFS
+	pub peak_level: f64,
+
+	/// RMS level in dBFS
+	pub rms_level: f64,
+
+	/// Mean amplitude of the signal, in the range of -1.0 to 1.0
+	pub dc_offset: f64,
+
+	/// Ratio between the peak and RMS amplitudes
+	pub crest_factor: f64,
+
+	/// RMS level in dBFS of the quietest window of the signal
+	pub noise_floor: f64,
+
+	/// Difference in dB between the peak level and the noise floor
+	pub dynamic_range: f64,
+
+	/// Number of zero crossings per sample
+	pub zero_crossing_rate: f64,
+
+	/// Number of samples in the channel
+	pub samples: u64,
+}
+
+#[derive(Default)]
+struct ChannelAccumulator {
+	peak: f64,
+	sum: f64,
+	sum_squares: f64,
+	samples: u64,
+	zero_crossings: u64,
+	negative: Option<bool>,
+
+	window_sum_squares: f64,
+	window_samples: u64,
+	min_window_rms: Option<f64>,
+}
+impl ChannelAccumulator {
+	fn push(&mut self, amplitude: f64, window: u64) {
+		self.peak = self.peak.max(amplitude.abs());
+		self.sum += amplitude;
+		self.sum_squares += amplitude * amplitude;
+		self.samples += 1;
+
+		let negative = amplitude < 0.0;
+		if self.negative.is_some_and(|last| last != negative) {
+			self.zero_crossings += 1;
+		}
+		self.negative = Some(negative);
+
+		self.window_sum_squares += amplitude * amplitude;
+		self.window_samples += 1;
+		if self.window_samples == window {
+			let rms = (self.window_sum_squares / self.window_samples as f64).sqrt();
+			self.min_window_rms = Some(self.min_window_rms.map_or(rms, |min| min.min(rms)));
+			self.window_sum_squares = 0.0;
+			self.window_samples = 0;
+		}
+	}
+
+	fn stats(&self) -> ChannelStats {
+		let samples = self.samples.max(1) as f64;
+		let rms = (self.sum_squares / samples).sqrt();
+
+		// If the channel is shorter than a single window, the whole channel is the quietest window.
+		let noise_floor = self.min_window_rms.unwrap_or(rms);
+
+		ChannelStats {
+			peak_level: util::to_db(self.peak),
+			rms_level: util::to_db(rms),
+			dc_offset: self.sum / samples,
+			crest_factor: self.peak / rms,
+			noise_floor: util::to_db(noise_floor),
+			dynamic_range: util::to_db(self.peak) - util::to_db(noise_floor),
+			zero_crossing_rate: self.zero_crossings as f64 / samples,
+			samples: self.samples,
+		}
+	}
+}
+
+pub(crate) struct StatsCollector {
+	/// Number of samples in each noise floor window
+	window: u64,
+	channels: Vec<ChannelAccumulator>,
+}
+impl StatsCollector {
+	pub fn new(sample_rate: u32) -> Self {
+		Self {
+			window: ((sample_rate as f64 * NOISE_FLOOR_WINDOW) as u64).max(1),
+			channels: Vec::new(),
+		}
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView) {
+		if self.channels.len() < frame.channels() {
+			self.channels.resize_with(frame.channels(), Default::default);
+		}
+
+		for (channel, accumulator) in self.channels.iter_mut().enumerate().take(frame.channels()) {
+			for sample in 0..frame.samples() {
+				accumulator.push(frame.sample(sample, channel).amplitude(), self.window);
+			}
+		}
+	}
+
+	pub fn stats(&self) -> Vec<ChannelStats> {
+		self.channels.iter().map(ChannelAccumulator::stats).collect()
+	}
+
+	pub fn write(&self, encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+		encoder.array(self.channels.len())?;
+
+		for stats in self.stats() {
+			encoder.map(8)?;
+			encoder.key("peak_level")?;
+			encoder.f64(stats.peak_level)?;
+			encoder.key("rms_level")?;
+			encoder.f64(stats.rms_level)?;
+			encoder.key("dc_offset")?;
+			encoder.f64(stats.dc_offset)?;
+			encoder.key("crest_factor")?;
+			encoder.f64(stats.crest_factor)?;
+			encoder.key("noise_floor")?;
+			encoder.f64(stats.noise_floor)?;
+			encoder.key("dynamic_range")?;
+			encoder.f64(stats.dynamic_range)?;
+			encoder.key("zero_crossing_rate")?;
+			encoder.f64(stats.zero_crossing_rate)?;
+			encoder.key("samples")?;
+			encoder.u64(stats.samples)?;
+			encoder.end()?;
+		}
+
+		encoder.end()
+	}
+}
@@ -0,0 +1,47 @@
+use crate::{
+	audio::{FrameView, PlanarSample},
+	util, FfAudio2Json,
+};
+use std::ops::Range;
+
+pub(crate) struct TrimDetector {
+	/// Linear amplitude at or above which a sample is considered audible
+	threshold: f64,
+
+	/// Number of samples to keep either side of the audible region
+	hold: u64,
+
+	/// The first and last audible sample positions
+	audible: Option<(u64, u64)>,
+}
+impl TrimDetector {
+	pub fn new(config: &FfAudio2Json, sample_rate: u32) -> Self {
+		Self {
+			threshold: util::from_db(config.trim_silence_threshold),
+			hold: (config.trim_silence_hold.max(0.0) * sample_rate as f64).round() as u64,
+			audible: None,
+		}
+	}
+
+	pub fn feed(&mut self, frame: &impl FrameView, position: u64) {
+		for sample in 0..frame.samples() {
+			if (0..frame.channels()).any(|channel| frame.sample(sample, channel).amplitude().abs() >= self.threshold) {
+				let position = position + sample as u64;
+				match self.audible {
+					Some((_, ref mut last)) => *last = position,
+					None => self.audible = Some((position, position)),
+				}
+			}
+		}
+	}
+
+	/// Returns the range of samples that should be kept.
+	///
+	/// If the stream is entirely silent, nothing is trimmed.
+	pub fn range(&self, samples: u64) -> Range<u64> {
+		match self.audible {
+			Some((first, last)) => first.saturating_sub(self.hold)..(last + 1).saturating_add(self.hold).min(samples),
+			None => 0..samples,
+		}
+	}
+}
@@ -7,8 +7,17 @@ pub trait PlanarSample: Clone + Copy + Sized + std::cmp::PartialOrd {
 	/// We always use `f64` as an intermediary value, this makes it easier to work with composite channels.
 	fn normalize(this: f64) -> f64;
 
+	/// Normalize the sample to a signed range of -1.0 to 1.0
+	fn normalize_signed(this: f64) -> f64;
+
 	/// Convert the sample to a `f64`
 	fn into_f64(self) -> f64;
+
+	/// Convert the sample to a signed amplitude in the range of -1.0 to 1.0, where 0.0 is silence
+	fn amplitude(self) -> f64;
+
+	/// Whether the sample is at (or for float formats, within `epsilon` of) full scale
+	fn is_full_scale(self, epsilon: f64) -> bool;
 }
 
 pub trait PackedSample: ffmpeg::frame::audio::Sample {
@@ -19,15 +28,113 @@ pub trait PackedSample: ffmpeg::frame::audio::Sample {
 	fn index(&self, channel: usize) -> Self::Planar;
 }
 
+/// A view over the samples of a decoded frame, regardless of whether its layout is planar or packed
+pub trait FrameView {
+	/// The planar sample type of the frame
+	type Sample: PlanarSample;
+
+	/// The number of samples (per channel) in the frame
+	fn samples(&self) -> usize;
+
+	/// The number of channels in the frame
+	fn channels(&self) -> usize;
+
+	/// Get the sample at the given index for the given channel
+	fn sample(&self, sample: usize, channel: usize) -> Self::Sample;
+}
+
+pub struct PlanarFrame<'a, T> {
+	planes: Vec<&'a [T]>,
+	samples: usize,
+}
+impl<'a, T: PlanarSample + ffmpeg::frame::audio::Sample> PlanarFrame<'a, T> {
+	pub fn new(frame: &'a ffmpeg::frame::Audio, channels: usize) -> Self {
+		Self {
+			planes: (0..channels).map(|plane| frame.plane::<T>(plane)).collect(),
+			samples: frame.samples(),
+		}
+	}
+}
+impl<T: PlanarSample> FrameView for PlanarFrame<'_, T> {
+	type Sample = T;
+
+	#[inline]
+	fn samples(&self) -> usize {
+		self.samples
+	}
+
+	#[inline]
+	fn channels(&self) -> usize {
+		self.planes.len()
+	}
+
+	#[inline]
+	fn sample(&self, sample: usize, channel: usize) -> T {
+		self.planes[channel][sample]
+	}
+}
+
+pub struct PackedFrame<'a, T> {
+	plane: &'a [T],
+	channels: usize,
+}
+impl<'a, T: PackedSample> PackedFrame<'a, T> {
+	pub fn new(plane: &'a [T], channels: usize) -> Self {
+		Self { plane, channels }
+	}
+}
+impl<T: PackedSample> FrameView for PackedFrame<'_, T> {
+	type Sample = T::Planar;
+
+	#[inline]
+	fn samples(&self) -> usize {
+		self.plane.len()
+	}
+
+	#[inline]
+	fn channels(&self) -> usize {
+		self.channels
+	}
+
+	#[inline]
+	fn sample(&self, sample: usize, channel: usize) -> T::Planar {
+		self.plane[sample].index(channel)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A single point of the waveform, flattened from a buffer of samples
+pub struct Point {
+	/// The maximum of the normalized samples, in the range of 0.0 to 1.0
+	pub peak: f64,
+
+	/// The minimum of the samples, in the range of -1.0 to 1.0
+	pub min: f64,
+
+	/// The maximum of the samples, in the range of -1.0 to 1.0
+	pub max: f64,
+}
+
 pub trait PlanarSampleIteratorEx<T: PlanarSample> {
-	/// "Flatten" the samples by normalizing them and returning the maximum value
-	fn flatten_samples<N: PlanarSample>(self) -> Option<f64>;
+	/// "Flatten" the samples by normalizing them and returning the maximum value, along with the signed minimum and maximum
+	fn flatten_samples<N: PlanarSample>(self) -> Option<Point>;
 }
 impl<T: PlanarSample, I: Iterator<Item = T>> PlanarSampleIteratorEx<T> for I {
-	fn flatten_samples<N: PlanarSample>(self) -> Option<f64> {
+	fn flatten_samples<N: PlanarSample>(self) -> Option<Point> {
 		self.map(PlanarSample::into_f64)
-			.map(N::normalize)
-			.reduce(|a, b| if a > b { a } else { b })
+			.map(|sample| {
+				let signed = N::normalize_signed(sample);
+				Point {
+					peak: N::normalize(sample),
+					min: signed,
+					max: signed,
+				}
+			})
+			.reduce(|a, b| Point {
+				peak: if a.peak > b.peak { a.peak } else { b.peak },
+				min: a.min.min(b.min),
+				max: a.max.max(b.max),
+			})
 	}
 }
 
@@ -46,10 +153,25 @@ macro_rules! impl_planar_sample {
 				(this.abs() / Self::MAX as f64).min(1.0)
 			}
 
+			#[inline]
+			fn normalize_signed(this: f64) -> f64 {
+				(this / Self::MAX as f64).clamp(-1.0, 1.0)
+			}
+
 			#[inline]
 			fn into_f64(self) -> f64 {
 				self as f64
 			}
+
+			#[inline]
+			fn amplitude(self) -> f64 {
+				self as f64 / -(Self::MIN as f64)
+			}
+
+			#[inline]
+			fn is_full_scale(self, _epsilon: f64) -> bool {
+				self == Self::MAX || self == Self::MIN
+			}
 		})*
 
 		$(impl PlanarSample for $signedf {
@@ -61,10 +183,25 @@ macro_rules! impl_planar_sample {
 				this.abs().min(1.0)
 			}
 
+			#[inline]
+			fn normalize_signed(this: f64) -> f64 {
+				this.clamp(-1.0, 1.0)
+			}
+
 			#[inline]
 			fn into_f64(self) -> f64 {
 				self as f64
 			}
+
+			#[inline]
+			fn amplitude(self) -> f64 {
+				self as f64
+			}
+
+			#[inline]
+			fn is_full_scale(self, epsilon: f64) -> bool {
+				(self as f64).abs() >= 1.0 - epsilon
+			}
 		})*
 
 		$(impl PlanarSample for $unsigned {
@@ -76,10 +213,28 @@ macro_rules! impl_planar_sample {
 				(this / Self::MAX as f64).min(1.0)
 			}
 
+			#[inline]
+			fn normalize_signed(this: f64) -> f64 {
+				let midpoint = (Self::MAX as f64 + 1.0) / 2.0;
+				((this - midpoint) / midpoint).clamp(-1.0, 1.0)
+			}
+
 			#[inline]
 			fn into_f64(self) -> f64 {
 				self as f64
 			}
+
+			#[inline]
+			fn amplitude(self) -> f64 {
+				// Unsigned samples are centered around the midpoint of their range
+				let midpoint = (Self::MAX as f64 + 1.0) / 2.0;
+				(self as f64 - midpoint) / midpoint
+			}
+
+			#[inline]
+			fn is_full_scale(self, _epsilon: f64) -> bool {
+				self == Self::MAX || self == Self::MIN
+			}
 		})*
 	};
 }
@@ -0,0 +1,88 @@
+//! Generating many waveforms at once on a pool of threads
+
+use crate::{Error, FfAudio2Json, Report};
+use std::{
+	collections::BTreeMap,
+	num::NonZeroUsize,
+	panic::AssertUnwindSafe,
+	sync::{mpsc, Mutex},
+};
+
+impl FfAudio2Json {
+	/// Generates the waveform of each configuration, running up to `jobs` of them at once on their own threads
+	///
+	/// If `jobs` is 0, the number of threads is the available parallelism of the machine.
+	///
+	/// `on_result` is called on the calling thread with the index of each configuration and its result, in the order of the configurations.
+	/// Configurations are only taken from the iterator when there is room for them, so that no more than a few of them per thread are
+	/// generating or waiting to be reported at any time, no matter how many there are.
+	pub fn run_batch(configs: impl IntoIterator<Item = FfAudio2Json>, jobs: usize, mut on_result: impl FnMut(usize, Result<Report, Error>)) {
+		let jobs = match jobs {
+			0 => std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+			jobs => jobs,
+		};
+
+		// The most configurations that can be taken from the iterator but not reported yet
+		let window = jobs * 2;
+
+		let (job_tx, job_rx) = mpsc::channel::<(usize, FfAudio2Json)>();
+		let (result_tx, result_rx) = mpsc::channel();
+
+		let job_rx = Mutex::new(job_rx);
+
+		std::thread::scope(|scope| {
+			for _ in 0..jobs {
+				let job_rx = &job_rx;
+				let result_tx = result_tx.clone();
+
+				scope.spawn(move || {
+					// The workers stop once all the jobs have been sent and the sender is dropped
+					while let Ok((index, config)) = { job_rx.lock().unwrap().recv() } {
+						// Panics are passed on to the calling thread, rather than leaving it waiting for a result that never arrives
+						let result = std::panic::catch_unwind(AssertUnwindSafe(|| config.run_with_report()));
+
+						if result_tx.send((index, result)).is_err() {
+							break;
+						}
+					}
+				});
+			}
+			drop(result_tx);
+
+			let mut configs = configs.into_iter();
+			let mut exhausted = false;
+			let mut dispatched = 0;
+			let mut reported = 0;
+
+			// Results that finished before the results of earlier configurations
+			let mut pending = BTreeMap::new();
+
+			loop {
+				while !exhausted && dispatched < reported + window {
+					match configs.next() {
+						Some(config) => {
+							job_tx.send((dispatched, config)).expect("workers stopped before all jobs were sent");
+							dispatched += 1;
+						}
+						None => exhausted = true,
+					}
+				}
+
+				if reported == dispatched {
+					break;
+				}
+
+				let (index, result) = result_rx.recv().expect("workers stopped before all results were received");
+				pending.insert(index, result.unwrap_or_else(|panic| std::panic::resume_unwind(panic)));
+
+				while let Some(result) = pending.remove(&reported) {
+					on_result(reported, result);
+					reported += 1;
+				}
+			}
+
+			// Lets the workers stop, which the scope waits for
+			drop(job_tx);
+		});
+	}
+}
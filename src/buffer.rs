@@ -1,5 +1,5 @@
 use crate::{
-	audio::{PlanarSample, PlanarSampleIteratorEx},
+	audio::{PlanarSample, PlanarSampleIteratorEx, Point},
 	util::unwrap_break,
 	Error,
 };
@@ -17,7 +17,7 @@ impl<Scalar: PlanarSample, Composite: PlanarSample> SampleBuffer<Scalar, Composi
 		}
 	}
 
-	pub fn push(&mut self, sample: Composite, mut process: impl FnMut(f64) -> Result<ControlFlow<()>, Error>) -> Result<ControlFlow<()>, Error> {
+	pub fn push(&mut self, sample: Composite, mut process: impl FnMut(Point) -> Result<ControlFlow<()>, Error>) -> Result<ControlFlow<()>, Error> {
 		if self.buffer.len() == self.buffer.capacity() {
 			unwrap_break!(process(self.buffer.drain(..).flatten_samples::<Scalar>().unwrap())?);
 		}
@@ -30,7 +30,7 @@ impl<Scalar: PlanarSample, Composite: PlanarSample> SampleBuffer<Scalar, Composi
 	pub fn extend(
 		&mut self,
 		mut samples: impl ExactSizeIterator<Item = Composite>,
-		mut process: impl FnMut(f64) -> Result<ControlFlow<()>, Error>,
+		mut process: impl FnMut(Point) -> Result<ControlFlow<()>, Error>,
 	) -> Result<ControlFlow<()>, Error> {
 		while self.buffer.len() + samples.len() > self.buffer.capacity() {
 			let samples_take = self.buffer.capacity() - self.buffer.len();
@@ -49,7 +49,7 @@ impl<Scalar: PlanarSample, Composite: PlanarSample> SampleBuffer<Scalar, Composi
 		Ok(ControlFlow::Continue(()))
 	}
 
-	pub fn flush(&mut self) -> Option<f64> {
+	pub fn flush(&mut self) -> Option<Point> {
 		let res = self.buffer.drain(..).flatten_samples::<Scalar>();
 		debug_assert!(self.buffer.drain(..).flatten_samples::<Scalar>().is_none());
 		res
@@ -78,12 +78,12 @@ fn test_sample_buffer_push() {
 
 		buffer
 			.push(0.6, |sample| {
-				assert_eq!(sample, 0.5);
+				assert_eq!(sample.peak, 0.5);
 				Ok(ControlFlow::Continue(()))
 			})
 			.unwrap();
 
-		assert_eq!(buffer.flush().unwrap(), 0.6);
+		assert_eq!(buffer.flush().unwrap().peak, 0.6);
 	}
 	assert_eq!(buffer.buffer.capacity(), 10);
 }
@@ -96,12 +96,12 @@ fn test_sample_buffer_extend() {
 
 		buffer
 			.extend(std::iter::once(0.6), |sample| {
-				assert_eq!(sample, 0.5);
+				assert_eq!(sample.peak, 0.5);
 				Ok(ControlFlow::Continue(()))
 			})
 			.unwrap();
 
-		assert_eq!(buffer.flush().unwrap(), 0.6);
+		assert_eq!(buffer.flush().unwrap().peak, 0.6);
 	}
 	assert_eq!(buffer.buffer.capacity(), 10);
 }
@@ -111,7 +111,18 @@ fn test_sample_buffer_flush() {
 	let mut buffer = SampleBuffer::<f64>::with_capacity(10);
 	assert!(buffer.flush().is_none());
 	buffer.push(0.5, |_sample| unreachable!()).unwrap();
-	assert_eq!(buffer.flush(), Some(0.5));
+	assert_eq!(buffer.flush().map(|point| point.peak), Some(0.5));
 	assert!(buffer.flush().is_none());
 	assert_eq!(buffer.buffer.capacity(), 10);
 }
+
+#[test]
+fn test_sample_buffer_min_max() {
+	let mut buffer = SampleBuffer::<i16>::with_capacity(10);
+	buffer.extend([-i16::MAX, 0, i16::MAX / 2].into_iter(), |_sample| unreachable!()).unwrap();
+
+	let point = buffer.flush().unwrap();
+	assert_eq!(point.peak, 1.0);
+	assert_eq!(point.min, -1.0);
+	assert!((point.max - 0.5).abs() < 0.001);
+}
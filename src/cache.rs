@@ -0,0 +1,187 @@
+//! Fingerprints of the inputs and configurations that outputs were generated from, for skipping outputs that are up to date
+
+use crate::{output::temp::TempFile, FfAudio2Json, GENERATOR};
+use std::{
+	fs::File,
+	io::{BufRead, BufReader, Write},
+	path::{Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+/// The extension of fingerprint files
+const EXTENSION: &str = "fingerprint";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Fingerprint {
+	/// The size of the input file, in bytes
+	size: u64,
+
+	/// The modification time of the input file, in nanoseconds since the Unix epoch
+	mtime: u128,
+
+	/// The hash of the content of the input file
+	hash: String,
+
+	/// The hash of the parts of the configuration that affect the output
+	config: String,
+
+	/// The size of the output file, in bytes, so that an output that was replaced since isn't reported as up to date
+	output_size: u64,
+
+	/// The modification time of the output file, in nanoseconds since the Unix epoch
+	output_mtime: u128,
+
+	/// The duration of the audio, so that it can be reported without decoding it
+	pub duration: f64,
+}
+impl Fingerprint {
+	/// Fingerprints the input, the configuration and the output at `output_path`, which doesn't need to exist
+	///
+	/// The content of the input is only hashed if its size or modification time differ from `previous`, as it can be expensive to read.
+	pub fn new(config: &FfAudio2Json, output_path: &Path, previous: Option<&Fingerprint>) -> Result<Self, std::io::Error> {
+		let (size, mtime) = size_and_mtime(&config.input)?;
+		let (output_size, output_mtime) = size_and_mtime(output_path).unwrap_or_default();
+
+		let hash = match previous {
+			Some(previous) if previous.size == size && previous.mtime == mtime => previous.hash.clone(),
+			_ => {
+				let mut hasher = blake3::Hasher::new();
+				hasher.update_reader(File::open(&config.input)?)?;
+				hasher.finalize().to_hex().to_string()
+			}
+		};
+
+		Ok(Self {
+			size,
+			mtime,
+			hash,
+			config: config.config_hash(),
+			output_size,
+			output_mtime,
+			duration: 0.0,
+		})
+	}
+
+	/// Whether an output generated with `self` is the same as one generated with `other`, and the output is still the one that was generated
+	pub fn matches(&self, other: &Fingerprint) -> bool {
+		self.hash == other.hash && self.config == other.config && self.output_size == other.output_size && self.output_mtime == other.output_mtime
+	}
+
+	/// Records the output at `output_path` once it has been generated
+	pub fn set_output(&mut self, output_path: &Path) -> Result<(), std::io::Error> {
+		(self.output_size, self.output_mtime) = size_and_mtime(output_path)?;
+		Ok(())
+	}
+
+	/// Reads a fingerprint, if there is a valid one at `path`
+	pub fn read(path: &Path) -> Option<Self> {
+		let mut size = None;
+		let mut mtime = None;
+		let mut hash = None;
+		let mut config = None;
+		let mut output_size = None;
+		let mut output_mtime = None;
+		let mut duration = None;
+
+		for line in BufReader::new(File::open(path).ok()?).lines() {
+			let line = line.ok()?;
+			let (key, value) = line.split_once('=')?;
+
+			match key {
+				"size" => size = value.parse().ok(),
+				"mtime" => mtime = value.parse().ok(),
+				"hash" => hash = Some(value.to_string()),
+				"config" => config = Some(value.to_string()),
+				"output_size" => output_size = value.parse().ok(),
+				"output_mtime" => output_mtime = value.parse().ok(),
+				"duration" => duration = value.parse().ok(),
+				_ => {}
+			}
+		}
+
+		Some(Self {
+			size: size?,
+			mtime: mtime?,
+			hash: hash?,
+			config: config?,
+			output_size: output_size?,
+			output_mtime: output_mtime?,
+			duration: duration?,
+		})
+	}
+
+	/// Writes the fingerprint to `path`, replacing it atomically
+	pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+		let temp = TempFile::create_for(path)?;
+
+		let mut file = File::create(temp.path())?;
+		writeln!(file, "size={}", self.size)?;
+		writeln!(file, "mtime={}", self.mtime)?;
+		writeln!(file, "hash={}", self.hash)?;
+		writeln!(file, "config={}", self.config)?;
+		writeln!(file, "output_size={}", self.output_size)?;
+		writeln!(file, "output_mtime={}", self.output_mtime)?;
+		writeln!(file, "duration={}", self.duration)?;
+		drop(file);
+
+		temp.persist(path, true)
+	}
+}
+
+/// The size of the file at `path` and its modification time in nanoseconds since the Unix epoch
+fn size_and_mtime(path: &Path) -> Result<(u64, u128), std::io::Error> {
+	let metadata = path.metadata()?;
+	Ok((
+		metadata.len(),
+		metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos(),
+	))
+}
+
+impl FfAudio2Json {
+	/// Where the fingerprint of the output at `output_path` is stored
+	pub(crate) fn fingerprint_path(&self, output_path: &Path) -> PathBuf {
+		match self.cache_dir {
+			// Outputs from anywhere can share the cache directory, so the fingerprint is named after the whole path of the output
+			Some(ref cache_dir) => {
+				let output_path = std::path::absolute(output_path).unwrap_or_else(|_| output_path.to_path_buf());
+				let name = blake3::hash(output_path.as_os_str().as_encoded_bytes()).to_hex();
+				cache_dir.join(format!("{name}.{EXTENSION}"))
+			}
+
+			None => {
+				let mut file_name = output_path.file_name().unwrap_or_default().to_os_string();
+				file_name.push(".");
+				file_name.push(EXTENSION);
+				output_path.with_file_name(file_name)
+			}
+		}
+	}
+
+	/// Hashes the parts of the configuration that affect the output, along with the version that generates it
+	fn config_hash(&self) -> String {
+		let relevant = format!(
+			"{GENERATOR} {:?}",
+			(
+				(self.samples, self.db_min, self.db_max, self.db_scale, self.precision, self.no_header),
+				(
+					self.detect_silence,
+					self.silence_threshold,
+					self.silence_min_duration,
+					self.silence_per_channel
+				),
+				(self.trim_silence, self.trim_silence_threshold, self.trim_silence_hold),
+				(
+					self.detect_clipping,
+					self.clipping_epsilon,
+					self.clipping_min_samples,
+					self.clipped_channel
+				),
+				(self.stats, self.correlation),
+				(self.format, self.encoding, self.json_style, self.bits, self.chunk_size, self.compress),
+				&self.channels,
+			)
+		);
+
+		blake3::hash(relevant.as_bytes()).to_hex().to_string()
+	}
+}
@@ -1,7 +1,21 @@
-use crate::{audio::PlanarSample, buffer::SampleBuffer, util, FfAudio2Json};
+use crate::{
+	audio::{PlanarSample, Point},
+	buffer::SampleBuffer,
+	output::{
+		self,
+		encoder::{self, EncoderKind},
+		ndjson::PointRecords,
+		OutputFormat, PointEncoding,
+	},
+	util, FfAudio2Json,
+};
+use base64::{
+	engine::general_purpose::{GeneralPurpose, STANDARD},
+	write::EncoderWriter,
+};
 use std::{
 	fs::File,
-	io::{BufWriter, Write},
+	io::{BufWriter, Seek, Write},
 	ops::ControlFlow,
 };
 
@@ -38,17 +52,78 @@ pub enum Channel {
 	Max,
 }
 
+/// Where a [`ChannelWriter`] puts its points
+enum ChannelSink {
+	/// Written as decimals or integers straight into the JSON array reserved for the channel
+	Json(BufWriter<File>),
+
+	/// Written as quantized bytes into a base64 string reserved for the channel
+	Base64(Box<EncoderWriter<'static, GeneralPurpose, BufWriter<File>>>),
+
+	/// Written as little-endian `f32`s or `u8`s straight into the region reserved for the channel in the raw binary format
+	Raw(BufWriter<File>),
+
+	/// Written as fixed-width floats straight into the array reserved for the channel in a MessagePack or CBOR document
+	Binary(BufWriter<File>, EncoderKind),
+
+	/// Appended to the end of the output in NDJSON records
+	Ndjson(PointRecords),
+}
+
 pub(crate) struct ChannelWriter {
-	inner: BufWriter<File>,
+	inner: Option<ChannelSink>,
+
+	/// Points kept in memory, for formats that can only be encoded once all the points are known, or to be returned in the [`Report`](crate::Report)
+	points: Option<Vec<Point>>,
+
 	pub(crate) written: usize,
 }
 impl ChannelWriter {
+	fn with_sink(sink: ChannelSink) -> Self {
+		Self {
+			inner: Some(sink),
+			points: None,
+			written: 0,
+		}
+	}
+
 	pub(crate) fn new(writer: BufWriter<File>) -> Self {
-		Self { inner: writer, written: 0 }
+		Self::with_sink(ChannelSink::Json(writer))
+	}
+
+	pub(crate) fn base64(mut writer: BufWriter<File>) -> Result<Self, std::io::Error> {
+		writer.write_all(b"\"")?;
+
+		Ok(Self::with_sink(ChannelSink::Base64(Box::new(EncoderWriter::new(writer, &STANDARD)))))
+	}
+
+	pub(crate) fn raw(writer: BufWriter<File>) -> Self {
+		Self::with_sink(ChannelSink::Raw(writer))
 	}
 
-	pub(crate) fn write(&mut self, mut sample: f64, config: &FfAudio2Json) -> Result<ControlFlow<()>, std::io::Error> {
-		debug_assert!(sample >= 0.0);
+	pub(crate) fn binary(writer: BufWriter<File>, kind: EncoderKind) -> Self {
+		Self::with_sink(ChannelSink::Binary(writer, kind))
+	}
+
+	pub(crate) fn ndjson(records: PointRecords) -> Self {
+		Self::with_sink(ChannelSink::Ndjson(records))
+	}
+
+	pub(crate) fn points(capacity: usize) -> Self {
+		Self {
+			inner: None,
+			points: Some(Vec::with_capacity(capacity)),
+			written: 0,
+		}
+	}
+
+	/// Also keep the points in memory, in addition to writing them
+	pub(crate) fn keep_points(&mut self, capacity: usize) {
+		self.points.get_or_insert_with(|| Vec::with_capacity(capacity));
+	}
+
+	pub(crate) fn write(&mut self, point: Point, config: &FfAudio2Json) -> Result<ControlFlow<()>, std::io::Error> {
+		debug_assert!(point.peak >= 0.0);
 
 		if self.written >= config.samples as usize {
 			return Ok(ControlFlow::Break(()));
@@ -56,24 +131,104 @@ impl ChannelWriter {
 
 		self.written += 1;
 
-		if config.db_scale {
-			sample = util::map2range(
-				if sample > 0.0 { 20.0 * sample.log10() } else { config.db_min },
-				config.db_min,
-				config.db_max,
-				0.0,
-				1.0,
-			);
+		if let Some(ref mut points) = self.points {
+			points.push(point);
 		}
 
-		if self.written != 1 {
-			write!(self.inner, ",")?;
-		}
+		let Some(ref mut inner) = self.inner else {
+			return Ok(ControlFlow::Continue(()));
+		};
 
-		write!(self.inner, "{sample:.precision$}", precision = config.precision)?;
+		let sample = scale_peak(point.peak, config);
+
+		match inner {
+			ChannelSink::Json(inner) => {
+				if self.written != 1 {
+					inner.write_all(output::separator(config.json_style).as_bytes())?;
+				}
+
+				match config.encoding {
+					PointEncoding::Decimal => write!(inner, "{sample:.precision$}", precision = config.precision)?,
+					_ => write!(inner, "{}", output::quantize(sample, config.bits))?,
+				}
+			}
+
+			ChannelSink::Base64(inner) => {
+				let sample = output::quantize(sample, config.bits);
+
+				if config.bits == 8 {
+					inner.write_all(&[sample as u8])?;
+				} else {
+					inner.write_all(&(sample as u16).to_le_bytes())?;
+				}
+			}
+
+			ChannelSink::Raw(inner) => {
+				if config.format == OutputFormat::Uint8 {
+					inner.write_all(&[output::quantize(sample, 8) as u8])?;
+				} else {
+					inner.write_all(&(sample as f32).to_le_bytes())?;
+				}
+			}
+
+			ChannelSink::Binary(inner, kind) => encoder::write_point(inner, *kind, sample as f32)?,
+
+			ChannelSink::Ndjson(records) => records.push(sample, config)?,
+		}
 
 		Ok(ControlFlow::Continue(()))
 	}
+
+	/// Finishes writing the points, flushing anything that is still buffered
+	///
+	/// Returns the offset in the output file of the end of the points, if they were written to it.
+	pub(crate) fn finish(&mut self) -> Result<Option<u64>, std::io::Error> {
+		let inner = match self.inner {
+			Some(ChannelSink::Json(ref mut inner)) => {
+				inner.write_all(b"]")?;
+				inner
+			}
+
+			Some(ChannelSink::Raw(ref mut inner) | ChannelSink::Binary(ref mut inner, _)) => inner,
+
+			Some(ChannelSink::Base64(ref mut inner)) => {
+				let mut inner = inner.finish()?;
+				inner.write_all(b"\"")?;
+				inner.flush()?;
+				return inner.stream_position().map(Some);
+			}
+
+			Some(ChannelSink::Ndjson(ref mut records)) => {
+				records.flush()?;
+				return Ok(None);
+			}
+
+			None => return Ok(None),
+		};
+
+		inner.flush()?;
+		inner.stream_position().map(Some)
+	}
+
+	/// The points that have been kept by this writer
+	pub(crate) fn as_points(&self) -> &[Point] {
+		self.points.as_deref().unwrap_or_default()
+	}
+}
+
+/// Maps the peak of a point onto the scale of the output, which is logarithmic if `db_scale` is enabled
+pub(crate) fn scale_peak(peak: f64, config: &FfAudio2Json) -> f64 {
+	if config.db_scale {
+		util::map2range(
+			if peak > 0.0 { 20.0 * peak.log10() } else { config.db_min },
+			config.db_min,
+			config.db_max,
+			0.0,
+			1.0,
+		)
+	} else {
+		peak
+	}
 }
 
 pub(crate) struct Channels<Scalar, Composite = Scalar> {
@@ -108,6 +263,39 @@ impl<Scalar, Composite> Channels<Scalar, Composite> {
 	}
 }
 impl<Scalar> Channels<Scalar, Scalar> {
+	pub fn get(&self, channel: Channel) -> Option<&Scalar> {
+		match channel {
+			Channel::Left => self.left.as_ref(),
+			Channel::Right => self.right.as_ref(),
+			Channel::Mid => self.mid.as_ref(),
+			Channel::Side => self.side.as_ref(),
+			Channel::Min => self.min.as_ref(),
+			Channel::Max => self.max.as_ref(),
+		}
+	}
+
+	pub fn set(&mut self, channel: Channel, value: Scalar) {
+		*match channel {
+			Channel::Left => &mut self.left,
+			Channel::Right => &mut self.right,
+			Channel::Mid => &mut self.mid,
+			Channel::Side => &mut self.side,
+			Channel::Min => &mut self.min,
+			Channel::Max => &mut self.max,
+		} = Some(value);
+	}
+
+	pub fn get_mut(&mut self, channel: Channel) -> Option<&mut Scalar> {
+		match channel {
+			Channel::Left => self.left.as_mut(),
+			Channel::Right => self.right.as_mut(),
+			Channel::Mid => self.mid.as_mut(),
+			Channel::Side => self.side.as_mut(),
+			Channel::Min => self.min.as_mut(),
+			Channel::Max => self.max.as_mut(),
+		}
+	}
+
 	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Scalar> {
 		[
 			self.left.as_mut(),
@@ -1,7 +1,14 @@
 //! Configuration for the behaviour of FfAudio2Json
 
-use crate::channels::Channel;
-use std::path::PathBuf;
+use crate::{
+	channels::Channel,
+	output::{compress::Compression, JsonStyle, OutputFormat, PointEncoding},
+	progress::{Progress, ProgressCallback},
+};
+use std::{
+	path::PathBuf,
+	sync::{atomic::AtomicBool, Arc},
+};
 
 #[derive(Debug, derive_builder::Builder)]
 #[builder(build_fn(validate = "Self::validate"))]
@@ -31,7 +38,153 @@ pub struct FfAudio2Json {
 	#[builder(default = "false")]
 	pub(crate) no_header: bool,
 
-	/// Name of output file, defaults to `<name of inputfile>.json`
+	/// Detect silent intervals in the audio and include them in the output
+	#[builder(default = "false")]
+	pub(crate) detect_silence: bool,
+
+	/// Level in dBFS below which a sample is considered silent
+	#[builder(default = "-60.0")]
+	pub(crate) silence_threshold: f64,
+
+	/// Minimum duration in seconds that the audio must stay below the threshold for to be reported as silent
+	#[builder(default = "0.5")]
+	pub(crate) silence_min_duration: f64,
+
+	/// Detect silence on each channel separately, rather than only when all channels are silent
+	#[builder(default = "false")]
+	pub(crate) silence_per_channel: bool,
+
+	/// Exclude leading and trailing silence from the waveform
+	///
+	/// The trimmed region is recorded in the output, so that points can still be mapped to the original timeline. The audiowaveform formats
+	/// have nowhere to record it, so they can't be trimmed.
+	#[builder(default = "false")]
+	pub(crate) trim_silence: bool,
+
+	/// Level in dBFS below which leading and trailing audio is trimmed
+	#[builder(default = "-60.0")]
+	pub(crate) trim_silence_threshold: f64,
+
+	/// Amount of audio in seconds to keep before the first and after the last sample above the trim threshold
+	#[builder(default = "0.1")]
+	pub(crate) trim_silence_hold: f64,
+
+	/// Detect clipping (runs of consecutive samples at full scale) and report the clipped regions in the output
+	#[builder(default = "false")]
+	pub(crate) detect_clipping: bool,
+
+	/// For float formats, how close to full scale (1.0) a sample must be to be considered clipped
+	#[builder(default = "0.0")]
+	pub(crate) clipping_epsilon: f64,
+
+	/// Minimum number of consecutive samples at full scale that are reported as clipping
+	#[builder(default = "3")]
+	pub(crate) clipping_min_samples: u32,
+
+	/// Output a `clipped` channel, aligned with the waveform, containing the number of clipped samples in each point
+	#[builder(default = "false")]
+	pub(crate) clipped_channel: bool,
+
+	/// Compute statistics (peak, RMS, DC offset, etc.) for each channel and include them in the output
+	#[builder(default = "false")]
+	pub(crate) stats: bool,
+
+	/// Compute the phase correlation between the left and right channels, for each point and for the whole stream
+	#[builder(default = "false")]
+	pub(crate) correlation: bool,
+
+	/// Keep the generated points in memory and return them in the [`Report`](crate::Report), for example to render them
+	#[builder(default = "false")]
+	pub(crate) keep_points: bool,
+
+	/// Format of the output file
+	#[builder(default)]
+	pub(crate) format: OutputFormat,
+
+	/// How the points of each channel are written in the JSON output
+	///
+	/// Quantized encodings map points to integers in the range of `0..2^bits`, and record the scale in the output. Points are unsigned peaks,
+	/// signed minimum and maximum pairs are written by the [`Dat`](OutputFormat::Dat) and
+	/// [`AudiowaveformJson`](OutputFormat::AudiowaveformJson) formats instead.
+	#[builder(default)]
+	pub(crate) encoding: PointEncoding,
+
+	/// How the JSON output is laid out
+	///
+	/// The layout is deterministic, and never contains any padding.
+	#[builder(default)]
+	pub(crate) json_style: JsonStyle,
+
+	/// Number of bits used to store each value in formats that store integers (8 or 16)
+	#[builder(default = "16")]
+	pub(crate) bits: u8,
+
+	/// Maximum number of points in each record of the [`Ndjson`](OutputFormat::Ndjson) format
+	///
+	/// Smaller records can be drawn sooner, at the cost of a larger output.
+	#[builder(default = "64")]
+	pub(crate) chunk_size: u32,
+
+	/// Compress the output file
+	///
+	/// The extension of the compression is appended to the default name of the output file. Requires the `compress` feature.
+	///
+	/// Formats that are written in order (`dat`, `audiowaveform-json`, `csv` and `tsv`) are compressed as they're written. The others
+	/// reserve space that is filled in once the points are known, so they're generated uncompressed first and then compressed.
+	#[builder(default = "None")]
+	pub(crate) compress: Option<Compression>,
+
+	/// Number of segments that the audio is split into and decoded in parallel, each on its own thread, or 0 for the available parallelism
+	///
+	/// Segments start on the boundaries of points, so the waveform is the same as when the audio is decoded serially. Decoding of each segment
+	/// starts a little before it, so that codecs produce the same samples at its start. Analysis needs the audio to be decoded in order,
+	/// so it's always decoded serially if any analysis is enabled. Progress isn't reported while segments are decoded.
+	#[builder(default = "1")]
+	pub(crate) segments: u32,
+
+	/// Callback that is invoked with the progress of the generator after every packet of the audio stream is decoded
+	#[builder(default = "None", setter(custom))]
+	pub(crate) progress: Option<ProgressCallback>,
+
+	/// Token that cancels generation when it's set to `true`, for example from another thread
+	///
+	/// It's checked after every packet of the audio stream is decoded. Generation then fails with [`Error::Cancelled`](crate::Error::Cancelled), and the partially written output is removed.
+	#[builder(default = "None")]
+	pub(crate) cancel: Option<Arc<AtomicBool>>,
+
+	/// Fail with [`Error::OutputExists`](crate::Error::OutputExists) instead of replacing an existing output file
+	///
+	/// The output is written to a temporary file next to the destination first, which is only moved into place once it's complete.
+	#[builder(default = "false")]
+	pub(crate) no_overwrite: bool,
+
+	/// Write [`Ndjson`](OutputFormat::Ndjson) output to the destination directly, so that its records can be read as they're generated
+	///
+	/// The output is no longer replaced atomically: an existing output is truncated once generation starts, and the partial output is
+	/// removed if generation fails. Only uncompressed NDJSON can be streamed in place.
+	#[builder(default = "false")]
+	pub(crate) stream_in_place: bool,
+
+	/// Skip generation if the output is up to date, according to a fingerprint of the input and of the configuration that it was generated with
+	///
+	/// The fingerprint is stored next to the output, in a file with the extension `.fingerprint` appended, unless a [`cache_dir`](crate::FfAudio2JsonBuilder::cache_dir)
+	/// is given. The content of the input is only hashed when its size or modification time change. Outputs are always generated if
+	/// [`keep_points`](crate::FfAudio2JsonBuilder::keep_points) is enabled. Requires the `cache` feature.
+	#[builder(default = "false")]
+	#[cfg_attr(not(feature = "cache"), allow(dead_code))]
+	pub(crate) cache: bool,
+
+	/// Directory to store the fingerprints of the [`cache`](crate::FfAudio2JsonBuilder::cache) in, rather than next to the outputs
+	#[builder(default = "None")]
+	#[cfg_attr(not(feature = "cache"), allow(dead_code))]
+	pub(crate) cache_dir: Option<PathBuf>,
+
+	/// Generate the output even if the [`cache`](crate::FfAudio2JsonBuilder::cache) says that it's up to date
+	#[builder(default = "false")]
+	#[cfg_attr(not(feature = "cache"), allow(dead_code))]
+	pub(crate) force: bool,
+
+	/// Name of output file, defaults to `<name of inputfile>.<extension of format>`
 	#[builder(default = "None")]
 	pub(crate) output: Option<PathBuf>,
 
@@ -50,11 +203,58 @@ impl FfAudio2Json {
 }
 
 impl FfAudio2JsonBuilder {
+	/// Callback that is invoked with the [`Progress`] of the generator after every packet of the audio stream is decoded
+	///
+	/// It's called once more with a fraction of 1.0 when decoding has finished. It runs on the decoding thread, so it should return quickly.
+	pub fn progress(&mut self, callback: impl Fn(&Progress) + Send + Sync + 'static) -> &mut Self {
+		self.progress = Some(Some(ProgressCallback::new(callback)));
+		self
+	}
+
 	fn validate(&self) -> Result<(), String> {
 		if self.channels.as_ref().is_some_and(|channels| channels.is_empty()) {
 			return Err("At least one channel must be specified".to_string());
 		}
 
+		if self.silence_min_duration.is_some_and(|duration| duration.is_nan() || duration < 0.0) {
+			return Err("Minimum silence duration must not be negative".to_string());
+		}
+
+		if self.trim_silence_hold.is_some_and(|hold| hold.is_nan() || hold < 0.0) {
+			return Err("Silence trim hold time must not be negative".to_string());
+		}
+
+		if self.trim_silence == Some(true) && matches!(self.format, Some(OutputFormat::Dat | OutputFormat::AudiowaveformJson)) {
+			return Err(format!(
+				"Silence can't be trimmed with the {} format, which can't record the trimmed region",
+				self.format.unwrap()
+			));
+		}
+
+		if self.stream_in_place == Some(true) && (self.format != Some(OutputFormat::Ndjson) || matches!(self.compress, Some(Some(_)))) {
+			return Err("Only uncompressed NDJSON can be streamed in place".to_string());
+		}
+
+		if self.clipping_epsilon.is_some_and(|epsilon| epsilon.is_nan() || epsilon < 0.0) {
+			return Err("Clipping epsilon must not be negative".to_string());
+		}
+
+		if self.bits.is_some_and(|bits| bits != 8 && bits != 16) {
+			return Err("Bits must be either 8 or 16".to_string());
+		}
+
+		if self.chunk_size == Some(0) {
+			return Err("Chunk size must not be zero".to_string());
+		}
+
+		if cfg!(not(feature = "compress")) && matches!(self.compress, Some(Some(_))) {
+			return Err("Compressed output requires the `compress` feature".to_string());
+		}
+
+		if cfg!(not(feature = "cache")) && self.cache == Some(true) {
+			return Err("Caching requires the `cache` feature".to_string());
+		}
+
 		Ok(())
 	}
 }
@@ -9,6 +9,19 @@ pub enum Error {
 	/// I/O error
 	Io(#[from] std::io::Error),
 
+	#[cfg(feature = "render")]
+	#[error("PNG encoding error: {0}")]
+	/// PNG encoding error
+	Png(#[from] png::EncodingError),
+
+	#[error("Output file already exists: {0}")]
+	/// The output file already exists, and [`no_overwrite`](crate::FfAudio2JsonBuilder::no_overwrite) was set
+	OutputExists(std::path::PathBuf),
+
+	#[error("Generation was cancelled")]
+	/// Generation was cancelled through the [`cancel`](crate::FfAudio2JsonBuilder::cancel) token
+	Cancelled,
+
 	#[error("Format not supported: {format:?}, {channels:?} channels")]
 	/// Unsupported format
 	UnsupportedFormat {
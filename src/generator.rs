@@ -1,11 +1,16 @@
 use crate::{
-	audio::{PackedSample, PlanarSample},
+	analysis::Analysis,
+	audio::{PackedFrame, PackedSample, PlanarFrame, PlanarSample},
 	buffer::SampleBuffer,
 	channels::{ChannelWriter, Channels},
+	progress::{Progress, ProgressCallback},
 	util::unwrap_break,
 	Error, FfAudio2Json,
 };
-use std::ops::ControlFlow;
+use std::{
+	ops::{ControlFlow, Range},
+	sync::atomic::Ordering,
+};
 
 type ChannelBuffers<Planar> = Channels<SampleBuffer<Planar>, SampleBuffer<Planar, f64>>;
 
@@ -24,12 +29,31 @@ pub(crate) struct GeneratorContext<'a> {
 	pub buffer_capacity: usize,
 	pub stream_idx: usize,
 	pub writers: Channels<ChannelWriter>,
+	pub analysis: Analysis,
+
+	/// The range of samples (per channel) that points are generated from
+	pub window: Range<u64>,
+
+	/// Where to report the progress of decoding to, if anywhere
+	pub progress: Option<&'a ProgressCallback>,
+
+	/// The duration of the audio stream in seconds, which progress is measured against
+	pub duration: f64,
+
+	/// The sample to seek to before decoding, when only a segment of the stream is decoded
+	///
+	/// The position of the decoded samples is then taken from the timestamp of the first frame, rather than counted from the start of the stream.
+	pub seek: Option<u64>,
 }
 impl<'a> GeneratorContext<'a> {
-	pub fn generate(mut self, ictx: &mut ffmpeg::format::context::Input, decoder: &mut ffmpeg::codec::decoder::Audio) -> Result<(), Error> {
+	pub fn generate(
+		mut self,
+		ictx: &mut ffmpeg::format::context::Input,
+		decoder: &mut ffmpeg::codec::decoder::Audio,
+	) -> Result<(Channels<ChannelWriter>, Analysis), Error> {
 		// If there aren't any audio channels, bail
 		if decoder.channels() == 0 {
-			return Ok(());
+			return Ok((self.writers, self.analysis));
 		}
 
 		macro_rules! decode {
@@ -84,13 +108,19 @@ impl<'a> GeneratorContext<'a> {
 				}
 			}};
 		}
-		decode! {
+		let result = decode! {
 			F32(f32),
 			I16(i16),
 			I32(i32),
 			F64(f64),
 			U8(u8),
-		}
+		};
+		result?;
+
+		let points = self.points();
+		self.analysis.finish(points);
+
+		Ok((self.writers, self.analysis))
 	}
 
 	fn decode<Planar: PlanarSample>(
@@ -103,49 +133,111 @@ impl<'a> GeneratorContext<'a> {
 		let mut channel_buffers = self.writers.make_buffers::<Planar>(self.buffer_capacity);
 		let stream_idx = self.stream_idx;
 
+		// Once all the points have been written, we only keep decoding if the analysis needs the rest of the stream.
+		// If there are no writers at all, we're only here for the analysis.
+		let mut overrun = self.writers.iter_mut().next().is_none();
+
+		// The number of samples (per channel) decoded so far
+		let mut position = 0_u64;
+
+		// The number of bytes of packets read so far, from any stream
+		let mut bytes_read = 0_u64;
+		let rate = decoder.rate() as f64;
+
+		let stream = ictx.stream(stream_idx).ok_or(ffmpeg::Error::StreamNotFound)?;
+		let time_base = f64::from(stream.time_base());
+		let start_time = Some(stream.start_time()).filter(|start_time| *start_time != i64::MIN).unwrap_or(0);
+
+		if let Some(seek) = self.seek {
+			// Seek to the last keyframe before the sample, in microseconds
+			let timestamp = ((seek as f64 / rate + start_time as f64 * time_base) * 1_000_000.0) as i64;
+			ictx.seek(timestamp, ..timestamp)?;
+		}
+		let mut positioned = self.seek.is_none();
+
 		macro_rules! frame_decoder {
-			($frame:expr) => {
+			($frame:expr) => {{
+				let frame = $frame;
+
+				if !positioned {
+					let timestamp = frame.timestamp().or(frame.pts()).ok_or(ffmpeg::Error::InvalidData)?;
+					position = ((timestamp - start_time) as f64 * time_base * rate).round().max(0.0) as u64;
+					positioned = true;
+				}
+
+				let window = self.frame_window(position, frame.samples());
+				position += frame.samples() as u64;
+
 				frame_decoder(
 					DecodingContext {
 						config: self.config,
 						writers: &mut self.writers,
 						channel_buffers: &mut channel_buffers,
+						analysis: &mut self.analysis,
 						channel_count,
+						overrun,
+						window,
 					},
-					$frame,
+					frame,
 				)
-			};
+			}};
 		}
 
 		macro_rules! assert_uniform_written {
 			() => {
 				// Assert all the writers have written the same amount of samples.
 				if cfg!(debug_assertions) {
-					let written = self.writers.iter_mut().next().unwrap().written;
-					self.writers.iter_mut().for_each(|writer| assert_eq!(writer.written, written));
+					let written = self.writers.iter_mut().next().map(|writer| writer.written);
+					if let Some(written) = written {
+						self.writers.iter_mut().for_each(|writer| assert_eq!(writer.written, written));
+					}
 				}
 			};
 		}
 
+		// Frames are decoded into the same buffer, which is unreferenced before each frame is received into it
+		let mut decoded = ffmpeg::frame::Audio::empty();
+
 		'overrun: {
-			for (_, packet) in ictx.packets().filter(|(this, _)| this.index() == stream_idx) {
+			for (stream, packet) in ictx.packets() {
+				bytes_read += packet.size() as u64;
+
+				if stream.index() != stream_idx {
+					continue;
+				}
+
+				if self.config.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+					return Err(Error::Cancelled);
+				}
+
 				decoder.send_packet(&packet)?;
 
-				let mut decoded = ffmpeg::frame::Audio::empty();
 				while decoder.receive_frame(&mut decoded).is_ok() {
 					if frame_decoder!(&decoded)?.is_break() {
-						break 'overrun;
+						overrun = true;
+						if !self.analysis.is_active() {
+							break 'overrun;
+						}
 					}
 				}
 				assert_uniform_written!();
+
+				self.report_progress(position as f64 / rate, bytes_read);
+
+				// The rest of the stream is only decoded if the analysis needs it
+				if position >= self.window.end && !self.analysis.is_active() {
+					break;
+				}
 			}
 
 			decoder.send_eof()?;
 
-			let mut decoded = ffmpeg::frame::Audio::empty();
 			while decoder.receive_frame(&mut decoded).is_ok() {
 				if frame_decoder!(&decoded)?.is_break() {
-					break 'overrun;
+					overrun = true;
+					if !self.analysis.is_active() {
+						break 'overrun;
+					}
 				}
 			}
 			assert_uniform_written!();
@@ -172,6 +264,15 @@ impl<'a> GeneratorContext<'a> {
 			}
 		}
 
+		if let Some(progress) = self.progress {
+			progress.report(&Progress {
+				time: position as f64 / rate,
+				fraction: 1.0,
+				points: self.points(),
+				bytes_read,
+			});
+		}
+
 		debug_assert!(
 			channel_buffers.iter_mut_scalar().all(|buffer| buffer.flush().is_none()),
 			"Scalar buffers not flushed (channel_count={channel_count})"
@@ -183,19 +284,61 @@ impl<'a> GeneratorContext<'a> {
 
 		Ok(())
 	}
+
+	/// The number of points that have been written to each channel so far
+	fn points(&mut self) -> usize {
+		self.writers.iter_mut().next().map(|writer| writer.written).unwrap_or(0)
+	}
+
+	fn report_progress(&mut self, time: f64, bytes_read: u64) {
+		if let Some(progress) = self.progress {
+			let fraction = if self.duration > 0.0 {
+				(time / self.duration).clamp(0.0, 1.0)
+			} else {
+				0.0
+			};
+
+			progress.report(&Progress {
+				time,
+				fraction,
+				points: self.points(),
+				bytes_read,
+			});
+		}
+	}
+
+	/// Returns the range of samples within a frame that fall inside the window
+	fn frame_window(&self, position: u64, samples: usize) -> Range<usize> {
+		let start = self.window.start.saturating_sub(position).min(samples as u64) as usize;
+		let end = self.window.end.saturating_sub(position).min(samples as u64) as usize;
+		start..end
+	}
 }
 
-struct DecodingContext<'a, 'b, 'c, Planar: PlanarSample> {
+struct DecodingContext<'a, 'b, 'c, 'd, Planar: PlanarSample> {
 	config: &'a FfAudio2Json,
 	writers: &'b mut Channels<ChannelWriter>,
 	channel_buffers: &'c mut ChannelBuffers<Planar>,
+	analysis: &'d mut Analysis,
 	channel_count: usize,
+	overrun: bool,
+
+	/// The range of samples within the frame that points are generated from
+	window: Range<usize>,
 }
-impl<Planar: PlanarSample> DecodingContext<'_, '_, '_, Planar> {
+impl<Planar: PlanarSample> DecodingContext<'_, '_, '_, '_, Planar> {
 	fn decode_planar_frame(self, decoded: &ffmpeg::frame::Audio) -> Result<ControlFlow<()>, Error>
 	where
 		Planar: ffmpeg::frame::audio::Sample,
 	{
+		if self.analysis.is_active() {
+			self.analysis.feed(&PlanarFrame::<Planar>::new(decoded, self.channel_count));
+		}
+
+		if self.overrun {
+			return Ok(ControlFlow::Break(()));
+		}
+
 		macro_rules! push_to_writer {
 			($sample:expr => $channel:ident) => {
 				if let Some(ref mut writer) = self.writers.$channel {
@@ -229,17 +372,19 @@ impl<Planar: PlanarSample> DecodingContext<'_, '_, '_, Planar> {
 				};
 			}
 
+			let plane = &decoded.plane::<Planar>(0)[self.window.clone()];
+
 			if let Some(buffer) = self.channel_buffers.iter_mut_scalar().next() {
-				dump_to_writer!(buffer => decoded.plane::<Planar>(0).iter().copied());
+				dump_to_writer!(buffer => plane.iter().copied());
 			}
 
 			if let Some(buffer) = self.channel_buffers.iter_mut_composite().next() {
-				dump_to_writer!(buffer => decoded.plane::<Planar>(0).iter().copied().map(|sample| sample.into_f64()));
+				dump_to_writer!(buffer => plane.iter().copied().map(|sample| sample.into_f64()));
 			}
 
 		// BTW, we also need to do this after flushing the first buffer, as it's the only buffer we pushed to.
 		} else {
-			for sample in 0..decoded.samples() {
+			for sample in self.window.clone() {
 				let mut min = Planar::MAX;
 				let mut max = Planar::MIN;
 				let mut sum = 0.0_f64;
@@ -284,9 +429,19 @@ impl<Planar: PlanarSample> DecodingContext<'_, '_, '_, Planar> {
 	fn decode_packed_frame<Packed: PackedSample<Planar = Planar>>(self, decoded: &ffmpeg::frame::Audio) -> Result<ControlFlow<()>, Error> {
 		debug_assert_eq!(decoded.planes(), 1);
 
-		let plane = decoded.plane::<Packed>(0);
+		let frame = decoded.plane::<Packed>(0);
+
+		debug_assert_eq!(frame.len() % self.channel_count, 0);
+
+		if self.analysis.is_active() {
+			self.analysis.feed(&PackedFrame::new(frame, self.channel_count));
+		}
+
+		if self.overrun {
+			return Ok(ControlFlow::Break(()));
+		}
 
-		debug_assert_eq!(plane.len() % self.channel_count, 0);
+		let plane = &frame[self.window.clone()];
 
 		if self.channel_count == 1 {
 			for sample in plane {
@@ -14,6 +14,9 @@
 //! |-----------------|---------|-----------------------------------------------------------------|
 //! | `static-ffmpeg` | No      | Statically links ffmpeg instead of linking to shared libraries. |
 //! | `build-ffmpeg`  | No      | Builds ffmpeg from source and statically links to it.           |
+//! | `render`        | No      | Renders waveforms to PNG and SVG images (enabled in the CLI).   |
+//! | `compress`      | No      | Compresses the output with gzip, brotli or zstd (CLI default).  |
+//! | `cache`         | No      | Skips inputs whose outputs are up to date (CLI default).        |
 //!
 //! ## Example Output
 //!
@@ -38,15 +41,25 @@ compile_error!("The 'build-ffmpeg' feature is not supported on Windows with the
 extern crate ffmpeg_next as ffmpeg;
 
 use crate::{
-	channels::{ChannelWriter, Channels},
+	analysis::{Analysis, Buckets},
+	channels::{scale_peak, ChannelWriter, Channels},
 	generator::GeneratorContext,
+	output::{
+		audiowaveform,
+		encoder::{self, Encoder, EncoderKind},
+		ndjson::{self, PointRecords},
+		raw, table,
+		temp::TempFile,
+	},
 };
 use std::{
 	borrow::Cow,
 	ffi::OsStr,
 	fs::{File, OpenOptions},
-	io::{BufWriter, Seek, SeekFrom, Write},
+	io::{BufWriter, Read, Seek, SeekFrom, Write},
+	ops::Range,
 	path::{Path, PathBuf},
+	sync::OnceLock,
 	time::{Duration, Instant},
 };
 
@@ -59,56 +72,212 @@ pub use config::*;
 mod channels;
 pub use channels::Channel;
 
+mod report;
+pub use report::{Report, Waveform};
+
+mod analysis;
+pub use analysis::ChannelStats;
+
+mod progress;
+pub use progress::Progress;
+
+mod output;
+pub use output::{compress::Compression, JsonStyle, OutputFormat, PointEncoding};
+
+#[cfg(feature = "render")]
+pub mod render;
+
 #[doc(hidden)]
 pub use strum::{IntoEnumIterator, VariantArray};
 
 mod audio;
+mod batch;
 mod buffer;
+#[cfg(feature = "cache")]
+mod cache;
 mod generator;
+mod segments;
 mod util;
 
-const JSON_HEADER: &str = concat!(
-	"\n  \"_generator\":\"ffaudio2json version ",
+const GENERATOR: &str = concat!(
+	"ffaudio2json version ",
 	env!("CARGO_PKG_VERSION"),
 	" on ",
 	env!("TARGET_PLATFORM"),
-	" (https://github.com/WilliamVenner/ffaudio2json)\","
+	" (https://github.com/WilliamVenner/ffaudio2json)"
 );
 
+/// Initializes FFmpeg, only the first time that it's called
+fn init() -> Result<(), Error> {
+	static INIT: OnceLock<Result<(), ffmpeg::Error>> = OnceLock::new();
+
+	Ok((*INIT.get_or_init(ffmpeg::init))?)
+}
+
+/// The part of a document that was reserved for the points of a channel
+struct Region {
+	channel: Channel,
+
+	/// The offset of the channel's key
+	start: u64,
+
+	/// The offset of the first point
+	values: u64,
+}
+
 impl FfAudio2Json {
 	/// Generate the JSON waveform.
 	///
 	/// Returns the path to the output file.
 	pub fn run(self) -> Result<PathBuf, Error> {
+		self.run_with_report().map(|report| report.output)
+	}
+
+	/// Generate the JSON waveform.
+	///
+	/// Returns a [`Report`] containing the path to the output file, along with any statistics that were computed.
+	pub fn run_with_report(self) -> Result<Report, Error> {
 		let now = Instant::now();
 
 		let input_file_size = self.input.metadata()?.len();
 
 		let output_path = self.output_file_path();
 
-		let mut output = BufWriter::new(File::create(&output_path)?);
+		// The points of an output that's up to date aren't known without decoding the input again, so it's only skipped if they weren't asked for
+		#[cfg(feature = "cache")]
+		let fingerprint = if self.cache && !self.keep_points {
+			let path = self.fingerprint_path(&output_path);
+			let previous = cache::Fingerprint::read(&path);
+			let mut fingerprint = cache::Fingerprint::new(&self, &output_path, previous.as_ref())?;
+
+			if let Some(previous) = previous.filter(|previous| !self.force && previous.matches(&fingerprint) && output_path.exists()) {
+				log::debug!("Output is up to date, skipping generation");
+
+				// Remember the new modification time of an input whose content didn't change, so that it isn't hashed again
+				fingerprint.duration = previous.duration;
+				if fingerprint != previous {
+					fingerprint.write(&path)?;
+				}
+
+				return Ok(Report {
+					output: output_path.into_owned(),
+					duration: previous.duration,
+					stats: None,
+					correlation: None,
+					waveform: None,
+					cached: true,
+				});
+			}
 
-		output.write_all(b"{")?;
+			Some((path, fingerprint))
+		} else {
+			None
+		};
 
-		if !self.no_header {
-			output.write_all(JSON_HEADER.as_bytes())?;
+		if self.no_overwrite && output_path.exists() {
+			return Err(Error::OutputExists(output_path.into_owned()));
 		}
 
-		ffmpeg::init()?;
+		// The output is generated in a temporary file next to the destination, which is only moved into place once it's complete.
+		// Temporary files are removed when they're dropped, so nothing is left behind if generation fails or is cancelled.
+		// NDJSON that's streamed in place is written to the destination directly instead, so that its records can be read as they're generated.
+		let temp = if self.stream_in_place {
+			TempFile::create_in_place(&output_path, !self.no_overwrite).map_err(|err| {
+				if err.kind() == std::io::ErrorKind::AlreadyExists {
+					Error::OutputExists(output_path.to_path_buf())
+				} else {
+					err.into()
+				}
+			})?
+		} else {
+			TempFile::create_for(&output_path)?
+		};
+		let report = self.generate(&output_path, temp.path())?;
 
-		let mut ictx = ffmpeg::format::input(&self.input)?;
+		let temp = match self.compress {
+			// Sequential formats were already compressed as they were written
+			Some(compression) if !self.format.is_sequential() => {
+				log::debug!("Compressing output with {compression}...");
 
-		let stream = ictx.streams().best(ffmpeg::media::Type::Audio).ok_or(ffmpeg::Error::StreamNotFound)?;
-		let stream_idx = stream.index();
+				let compressed = TempFile::create_for(&output_path)?;
+				output::compress::compress_file(temp.path(), compressed.path(), compression)?;
+				compressed
+			}
+			_ => temp,
+		};
 
-		let codec = ffmpeg::codec::decoder::find(stream.parameters().id())
-			.ok_or(ffmpeg::Error::DecoderNotFound)?
-			.audio()?;
+		temp.persist(&output_path, !self.no_overwrite).map_err(|err| {
+			if err.kind() == std::io::ErrorKind::AlreadyExists {
+				Error::OutputExists(output_path.to_path_buf())
+			} else {
+				err.into()
+			}
+		})?;
 
-		let mut decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())?
-			.decoder()
-			.open_as(codec)?
-			.audio()?;
+		#[cfg(feature = "cache")]
+		if let Some((path, mut fingerprint)) = fingerprint {
+			if let Some(ref cache_dir) = self.cache_dir {
+				std::fs::create_dir_all(cache_dir)?;
+			}
+
+			fingerprint.duration = report.duration;
+			fingerprint.set_output(&output_path)?;
+			fingerprint.write(&path)?;
+		}
+
+		let elapsed = now.elapsed();
+		log::debug!(
+			"Took {:?} ({:.2} MiB/s) ({:?} of audio/s)",
+			elapsed,
+			input_file_size as f64 / elapsed.as_secs_f64() / 1024.0 / 1024.0,
+			Duration::try_from_secs_f64(report.duration / elapsed.as_secs_f64()).unwrap_or(Duration::ZERO)
+		);
+
+		Ok(report)
+	}
+
+	/// Removes the output file, for when its input has been removed.
+	///
+	/// The fingerprint of the output is removed too if [`cache`](crate::FfAudio2JsonBuilder::cache) is enabled. The input doesn't need to exist.
+	///
+	/// Returns the path to the output file, or `None` if there was no output file.
+	pub fn remove_output(&self) -> Result<Option<PathBuf>, Error> {
+		let output_path = self.output_file_path();
+
+		#[cfg(feature = "cache")]
+		if self.cache {
+			match std::fs::remove_file(self.fingerprint_path(&output_path)) {
+				Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+				_ => {}
+			}
+		}
+
+		match std::fs::remove_file(&output_path) {
+			Ok(()) => Ok(Some(output_path.into_owned())),
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(err) => Err(err.into()),
+		}
+	}
+
+	/// Generates the waveform into the file at `generate_path`, reporting `output_path` as the path of the output
+	fn generate(&self, output_path: &Path, generate_path: &Path) -> Result<Report, Error> {
+		// Formats that aren't documents write to the file directly, so the kind of encoder doesn't matter for them
+		let mut output = Encoder::new(
+			BufWriter::new(File::create(generate_path)?),
+			self.format.encoder_kind().unwrap_or(EncoderKind::Json),
+			// Every record of NDJSON has to be on a single line
+			if self.format == OutputFormat::Ndjson {
+				JsonStyle::Minified
+			} else {
+				self.json_style
+			},
+		);
+
+		init()?;
+
+		let (mut ictx, stream_idx, codec, mut decoder) = self.open_input()?;
+
+		let stream = ictx.stream(stream_idx).ok_or(ffmpeg::Error::StreamNotFound)?;
 
 		let input_duration = Some(stream.duration())
 			.filter(|duration| *duration != i64::MIN)
@@ -132,13 +301,89 @@ impl FfAudio2Json {
 			.expect("unable to determine stream duration") as f64
 			* f64::from(stream.time_base());
 
-		let input_samples = input_duration * decoder.rate() as f64;
+		let (window, waveform_duration) = if self.trim_silence {
+			log::debug!("Detecting leading and trailing silence...");
+
+			let window = self.detect_trim()?;
+			let waveform_duration = (window.end - window.start) as f64 / decoder.rate() as f64;
+
+			log::debug!(
+				"Trimmed to {:?}..{:?}",
+				Duration::from_secs_f64(window.start as f64 / decoder.rate() as f64),
+				Duration::from_secs_f64(window.end as f64 / decoder.rate() as f64),
+			);
+
+			(window, waveform_duration)
+		} else {
+			(0..u64::MAX, input_duration)
+		};
+
+		let input_samples = waveform_duration * decoder.rate() as f64;
 		let resample_rate = {
-			let dst_sample_rate = input_duration / (self.samples as f64).min(input_samples);
+			let dst_sample_rate = waveform_duration / (self.samples as f64).min(input_samples);
 			(dst_sample_rate * decoder.rate() as f64) as usize
 		};
 
-		let writers = self.writers(&mut output, &output_path, input_samples.ceil() as usize)?;
+		let raw_header = raw::Header {
+			format: self.format,
+			channels: &self.channels,
+			points: (self.samples as usize).min(input_samples.ceil() as usize) as u32,
+			sample_rate: decoder.rate(),
+			duration: waveform_duration,
+			start: window.start as f64 / decoder.rate() as f64,
+		};
+
+		let analysis = Analysis::new(
+			self,
+			decoder.rate(),
+			Buckets {
+				window: window.clone(),
+				capacity: resample_rate as u64,
+				points: self.samples as u64,
+			},
+		);
+
+		let mut regions = Vec::new();
+
+		let mut writers = match self.format {
+			OutputFormat::Json | OutputFormat::MessagePack | OutputFormat::Cbor => {
+				let keys = !self.no_header as usize
+					+ self.channels.len()
+					+ (self.format == OutputFormat::Json && self.encoding != PointEncoding::Decimal) as usize
+					+ analysis.keys()
+					+ self.trim_silence as usize
+					+ 1;
+
+				output.map(keys)?;
+
+				if !self.no_header {
+					output.key("_generator")?;
+					output.str(GENERATOR)?;
+				}
+
+				self.writers(&mut output, generate_path, input_samples.ceil() as usize, &mut regions)?
+			}
+			OutputFormat::Dat | OutputFormat::AudiowaveformJson | OutputFormat::Csv | OutputFormat::Tsv => {
+				self.point_writers(input_samples.ceil() as usize)
+			}
+			OutputFormat::Float32 | OutputFormat::Uint8 => self.raw_writers(output.get_mut(), generate_path, &raw_header)?,
+			OutputFormat::Ndjson => {
+				ndjson::Header {
+					sample_rate: decoder.rate(),
+					samples_per_point: resample_rate as u64,
+					points: raw_header.points as u64,
+					duration: input_duration,
+				}
+				.write(&mut output, self, GENERATOR)?;
+
+				self.ndjson_writers(generate_path)?
+			}
+		};
+
+		if self.keep_points {
+			let capacity = (self.samples as usize).min(input_samples.ceil() as usize);
+			writers.iter_mut().for_each(|writer| writer.keep_points(capacity));
+		}
 
 		log::debug!(
 			"Audio duration: {:?} ({} samples)",
@@ -156,57 +401,292 @@ impl FfAudio2Json {
 
 		log::debug!("Generating waveform...",);
 
-		output.flush()?;
-		GeneratorContext::generate(
+		output.get_mut().flush()?;
+
+		let segments = self.segment_count(raw_header.points as u64);
+
+		let (mut writers, analysis) = if segments > 1 && !analysis.is_active() {
+			let writers = self.generate_segments(writers, &window, resample_rate as u64, raw_header.points as u64, segments)?;
+			(writers, analysis)
+		} else {
+			if segments > 1 {
+				log::debug!("Analysis needs the whole stream in order, so it's decoded serially");
+			}
+
+			GeneratorContext::generate(
+				GeneratorContext {
+					writers,
+					buffer_capacity: resample_rate,
+					config: self,
+					stream_idx,
+					analysis,
+					window: window.clone(),
+					progress: self.progress.as_ref(),
+					duration: input_duration,
+					seek: None,
+				},
+				&mut ictx,
+				&mut decoder,
+			)?
+		};
+		let mut ends = Channels::default();
+		for channel in self.channels.iter().copied() {
+			if let Some(end) = writers.get_mut(channel).map(ChannelWriter::finish).transpose()?.flatten() {
+				ends.set(channel, end);
+			}
+		}
+		output.get_mut().flush()?;
+
+		match self.format {
+			OutputFormat::Json | OutputFormat::MessagePack | OutputFormat::Cbor => {
+				if !regions.is_empty() {
+					// Remove the space that was reserved for points that were never generated
+					let points = writers.iter_mut().next().map(|writer| writer.written).unwrap_or(0);
+					let end = self.finish_regions(generate_path, output.kind(), &regions, &ends, points)?;
+					output.get_mut().seek(SeekFrom::Start(end))?;
+				}
+
+				if self.format == OutputFormat::Json && self.encoding != PointEncoding::Decimal {
+					output.key("scale")?;
+					output.u64(output::quantize_scale(self.bits) as u64)?;
+				}
+
+				analysis.write(&mut output)?;
+
+				if self.trim_silence {
+					Self::write_trim(&mut output, &window, decoder.rate())?;
+				}
+
+				output.key("duration")?;
+				output.f64(input_duration)?;
+				output.end()?;
+
+				if !regions.is_empty() {
+					let end = output.get_mut().stream_position()?;
+					output.get_mut().get_ref().set_len(end)?;
+				}
+			}
+
+			OutputFormat::Dat | OutputFormat::AudiowaveformJson => {
+				let channels = self.channels.iter().filter_map(|channel| writers.get(*channel)).collect::<Vec<_>>();
+
+				let header = audiowaveform::Header {
+					sample_rate: decoder.rate(),
+					samples_per_pixel: resample_rate as u32,
+					bits: self.bits,
+				};
+
+				output::compress::write_compressed(output.get_mut(), self.compress, |mut output| {
+					if self.format == OutputFormat::Dat {
+						audiowaveform::write_dat(&mut output, header, &channels)
+					} else {
+						audiowaveform::write_json(&mut output, header, &channels)
+					}
+				})?;
+			}
+
+			OutputFormat::Csv | OutputFormat::Tsv => {
+				let channels = self
+					.channels
+					.iter()
+					.filter_map(|channel| writers.get(*channel).map(|writer| (*channel, writer)))
+					.collect::<Vec<_>>();
+
+				output::compress::write_compressed(output.get_mut(), self.compress, |mut output| {
+					table::write(
+						&mut output,
+						if self.format == OutputFormat::Csv { ',' } else { '\t' },
+						table::Timeline {
+							sample_rate: decoder.rate(),
+							samples_per_point: resample_rate as u64,
+							start: window.start,
+						},
+						&channels,
+						self,
+					)
+				})?;
+			}
+
+			OutputFormat::Float32 | OutputFormat::Uint8 => {
+				let points = writers.iter_mut().next().map(|writer| writer.written).unwrap_or(0);
+				raw::finish(&mut self.open_output_file_writer(generate_path)?, &raw_header, points)?;
+			}
+
+			OutputFormat::Ndjson => {
+				// The points records were appended after the header by other handles
+				output.get_mut().seek(SeekFrom::End(0))?;
+
+				ndjson::start(&mut output, "footer")?;
+
+				output.key("points")?;
+				output.u64(writers.iter_mut().next().map(|writer| writer.written).unwrap_or(0) as u64)?;
+
+				analysis.write(&mut output)?;
+
+				if self.trim_silence {
+					Self::write_trim(&mut output, &window, decoder.rate())?;
+				}
+
+				output.key("duration")?;
+				output.f64(input_duration)?;
+
+				ndjson::end(&mut output)?;
+			}
+		}
+
+		output.get_mut().flush()?;
+		drop(output);
+
+		Ok(Report {
+			output: output_path.to_path_buf(),
+			cached: false,
+			duration: input_duration,
+			stats: analysis.stats.as_ref().map(|stats| stats.stats()),
+			correlation: analysis.correlation.as_ref().map(|correlation| correlation.total()),
+			waveform: self.keep_points.then(|| Waveform {
+				channels: self
+					.channels
+					.iter()
+					.filter_map(|channel| {
+						let points = writers.get(*channel)?.as_points();
+						Some((*channel, points.iter().map(|point| scale_peak(point.peak, self)).collect()))
+					})
+					.collect(),
+				start: if self.trim_silence {
+					window.start as f64 / decoder.rate() as f64
+				} else {
+					0.0
+				},
+				duration: waveform_duration,
+			}),
+		})
+	}
+
+	/// Writes the range of the audio that remained after trimming silence, in seconds
+	fn write_trim(output: &mut Encoder<impl Write>, window: &Range<u64>, rate: u32) -> Result<(), std::io::Error> {
+		output.key("trim")?;
+		output.map(2)?;
+		output.key("start")?;
+		output.f64(window.start as f64 / rate as f64)?;
+		output.key("end")?;
+		output.f64(window.end as f64 / rate as f64)?;
+		output.end()
+	}
+
+	/// Opens the input file and a decoder for its best audio stream
+	fn open_input(&self) -> Result<(ffmpeg::format::context::Input, usize, ffmpeg::codec::Audio, ffmpeg::codec::decoder::Audio), Error> {
+		let ictx = ffmpeg::format::input(&self.input)?;
+
+		let stream = ictx.streams().best(ffmpeg::media::Type::Audio).ok_or(ffmpeg::Error::StreamNotFound)?;
+		let stream_idx = stream.index();
+
+		let codec = ffmpeg::codec::decoder::find(stream.parameters().id())
+			.ok_or(ffmpeg::Error::DecoderNotFound)?
+			.audio()?;
+
+		let decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())?
+			.decoder()
+			.open_as(codec)?
+			.audio()?;
+
+		Ok((ictx, stream_idx, codec, decoder))
+	}
+
+	/// Decodes the whole stream once to find the range of samples that remains after trimming leading and trailing silence
+	fn detect_trim(&self) -> Result<Range<u64>, Error> {
+		let (mut ictx, stream_idx, _, mut decoder) = self.open_input()?;
+
+		let (_, analysis) = GeneratorContext::generate(
 			GeneratorContext {
-				writers,
-				buffer_capacity: resample_rate,
-				config: &self,
+				writers: Channels::default(),
+				buffer_capacity: 0,
+				config: self,
 				stream_idx,
+				analysis: Analysis::for_trim(self, decoder.rate()),
+				window: 0..u64::MAX,
+				progress: None,
+				duration: 0.0,
+				seek: None,
 			},
 			&mut ictx,
 			&mut decoder,
 		)?;
-		output.flush()?;
-
-		write!(output, "\n  \"duration\":{input_duration}\n}}")?;
-		output.flush()?;
 
-		let elapsed = now.elapsed();
-		log::debug!(
-			"Took {:?} ({:.2} MiB/s) ({:?} of audio/s)",
-			elapsed,
-			input_file_size as f64 / elapsed.as_secs_f64() / 1024.0 / 1024.0,
-			Duration::try_from_secs_f64(input_duration / elapsed.as_secs_f64()).unwrap_or(Duration::ZERO)
-		);
-
-		Ok(output_path.into_owned())
+		Ok(analysis.trim.as_ref().map(|trim| trim.range(analysis.position())).unwrap_or(0..u64::MAX))
 	}
 
-	fn writers(&self, output: &mut (impl Write + Seek), output_path: &Path, input_samples: usize) -> Result<Channels<ChannelWriter>, Error> {
+	fn writers(
+		&self,
+		output: &mut Encoder<impl Write + Seek>,
+		output_path: &Path,
+		input_samples: usize,
+		regions: &mut Vec<Region>,
+	) -> Result<Channels<ChannelWriter>, Error> {
 		let mut writers = Channels::<ChannelWriter>::default();
 
-		let samples_width = ((self.samples as usize).min(input_samples) * (self.precision + 3)).saturating_sub(1);
+		let points = (self.samples as usize).min(input_samples);
+
+		let kind = output.kind();
+
+		if kind != EncoderKind::Json {
+			for channel in self.channels.iter().copied() {
+				let start = output.get_mut().stream_position()?;
+
+				output.key(&channel.to_string())?;
+				encoder::reserve_array(output.get_mut(), kind, points as u32)?;
+
+				let values = output.get_mut().stream_position()?;
+
+				let mut writer = self.open_output_file_writer(output_path)?;
+				writer.seek(SeekFrom::Start(values))?;
+				writers.set(channel, ChannelWriter::binary(BufWriter::new(writer), kind));
+
+				std::io::copy(&mut std::io::repeat(0).take(points as u64 * encoder::POINT_SIZE), output.get_mut())?;
+
+				regions.push(Region { channel, start, values });
+			}
+
+			return Ok(writers);
+		}
+
+		// The widest that the points can be, including the brackets or quotes around them
+		let separator = output::separator(self.json_style).len();
+		let samples_width = match self.encoding {
+			PointEncoding::Decimal => (points * (self.precision + 2 + separator)).saturating_sub(separator) + 2,
+			PointEncoding::Integer => (points * (output::quantize_scale(self.bits).to_string().len() + separator)).saturating_sub(separator) + 2,
+			PointEncoding::Base64 => (points * (self.bits as usize / 8)).div_ceil(3) * 4 + 2,
+		};
 
 		self.channels.iter().copied().try_for_each(|channel| {
-			write!(output, "\n  \"{channel}\":[")?;
+			let start = output.get_mut().stream_position()?;
+
+			output.key(&channel.to_string())?;
+
+			let output = output.get_mut();
+			let values = output.stream_position()?;
+
+			// The opening quote of base64 strings is written by the writer, and the closing bracket or quote by `ChannelWriter::finish`
+			if self.encoding != PointEncoding::Base64 {
+				write!(output, "[")?;
+			}
 
-			let writer = ChannelWriter::new({
+			let writer = {
 				let mut writer = self.open_output_file_writer(output_path)?;
 				writer.seek(SeekFrom::Start(output.stream_position()?))?;
-				BufWriter::new(writer)
-			});
 
-			match channel {
-				Channel::Left => writers.left = Some(writer),
-				Channel::Right => writers.right = Some(writer),
-				Channel::Mid => writers.mid = Some(writer),
-				Channel::Side => writers.side = Some(writer),
-				Channel::Min => writers.min = Some(writer),
-				Channel::Max => writers.max = Some(writer),
-			}
+				if self.encoding == PointEncoding::Base64 {
+					ChannelWriter::base64(BufWriter::new(writer))?
+				} else {
+					ChannelWriter::new(BufWriter::new(writer))
+				}
+			};
+
+			writers.set(channel, writer);
+
+			let padding = samples_width - (output.stream_position()? - values) as usize;
+			write!(output, "{:padding$}", ' ')?;
 
-			write!(output, "{:samples_width$}],", ' ', samples_width = samples_width)?;
+			regions.push(Region { channel, start, values });
 
 			Ok::<_, std::io::Error>(())
 		})?;
@@ -214,11 +694,77 @@ impl FfAudio2Json {
 		Ok(writers)
 	}
 
+	/// Patches the length of the array reserved for each channel of a binary document,
+	/// and removes the space that was reserved for points that were never generated.
+	///
+	/// Returns the offset of the end of the last array.
+	fn finish_regions(&self, output_path: &Path, kind: EncoderKind, regions: &[Region], ends: &Channels<u64>, points: usize) -> Result<u64, Error> {
+		let mut file = self.open_output_file_writer(output_path)?;
+
+		if kind != EncoderKind::Json {
+			for region in regions {
+				file.seek(SeekFrom::Start(region.values - encoder::ARRAY_HEAD_SIZE))?;
+				encoder::reserve_array(&mut file, kind, points as u32)?;
+			}
+		}
+
+		let blocks = regions
+			.iter()
+			.map(|region| region.start..ends.get(region.channel).copied().unwrap_or(region.values))
+			.collect::<Vec<_>>();
+
+		Ok(output::compact(&mut file, &blocks)?)
+	}
+
+	fn raw_writers(&self, output: &mut impl Write, output_path: &Path, header: &raw::Header) -> Result<Channels<ChannelWriter>, Error> {
+		let mut writers = Channels::<ChannelWriter>::default();
+
+		header.write(output)?;
+
+		for (n, channel) in self.channels.iter().copied().enumerate() {
+			let mut writer = self.open_output_file_writer(output_path)?;
+			writer.seek(SeekFrom::Start(header.channel_offset(n)))?;
+
+			writers.set(channel, ChannelWriter::raw(BufWriter::new(writer)));
+		}
+
+		Ok(writers)
+	}
+
+	/// Writers that append records of points to the output as soon as they are generated
+	fn ndjson_writers(&self, output_path: &Path) -> Result<Channels<ChannelWriter>, Error> {
+		let mut writers = Channels::<ChannelWriter>::default();
+
+		for channel in self.channels.iter().copied() {
+			let file = OpenOptions::new().append(true).open(output_path)?;
+			writers.set(channel, ChannelWriter::ndjson(PointRecords::new(file, channel, self)));
+		}
+
+		Ok(writers)
+	}
+
+	/// Writers that keep the points in memory, for formats that are encoded once generation has finished
+	fn point_writers(&self, input_samples: usize) -> Channels<ChannelWriter> {
+		let mut writers = Channels::<ChannelWriter>::default();
+
+		for channel in self.channels.iter().copied() {
+			writers.set(channel, ChannelWriter::points((self.samples as usize).min(input_samples)));
+		}
+
+		writers
+	}
+
 	fn output_file_path(&self) -> Cow<'_, Path> {
 		self.output.as_deref().map(Cow::Borrowed).unwrap_or_else(|| {
 			let mut file_name = self.input.file_name().unwrap_or(OsStr::new("output")).to_os_string();
 
-			file_name.push(OsStr::new(".json"));
+			file_name.push(OsStr::new("."));
+			file_name.push(OsStr::new(self.format.extension()));
+
+			if let Some(compression) = self.compress {
+				file_name.push(OsStr::new("."));
+				file_name.push(OsStr::new(compression.extension()));
+			}
 
 			Cow::Owned(self.input.with_file_name(file_name))
 		})
@@ -231,7 +777,7 @@ impl FfAudio2Json {
 			.write(true)
 			.truncate(false)
 			.append(false)
-			.read(false)
+			.read(true)
 			.open(path)
 	}
 }
@@ -0,0 +1,89 @@
+//! Encoders for the [audiowaveform](https://github.com/bbc/audiowaveform/blob/master/doc/DataFormat.md) binary data format and its JSON equivalent
+
+use crate::{audio::Point, channels::ChannelWriter};
+use std::io::Write;
+
+/// Set in the header flags when the data is stored as 8-bit values
+const FLAG_8_BIT: u32 = 1;
+
+pub(crate) struct Header {
+	pub sample_rate: u32,
+	pub samples_per_pixel: u32,
+	pub bits: u8,
+}
+
+/// Writes the header and the interleaved min/max pairs of each channel in the binary format
+///
+/// Version 1 of the format is used for a single channel, as it is the most widely supported, and version 2 otherwise.
+pub(crate) fn write_dat(output: &mut impl Write, header: Header, channels: &[&ChannelWriter]) -> Result<(), std::io::Error> {
+	let length = length(channels);
+
+	let version: i32 = if channels.len() == 1 { 1 } else { 2 };
+	let flags = if header.bits == 8 { FLAG_8_BIT } else { 0 };
+
+	output.write_all(&version.to_le_bytes())?;
+	output.write_all(&flags.to_le_bytes())?;
+	output.write_all(&(header.sample_rate as i32).to_le_bytes())?;
+	output.write_all(&(header.samples_per_pixel as i32).to_le_bytes())?;
+	output.write_all(&(length as u32).to_le_bytes())?;
+
+	if version == 2 {
+		output.write_all(&(channels.len() as i32).to_le_bytes())?;
+	}
+
+	for (min, max) in pairs(&header, channels) {
+		if header.bits == 8 {
+			output.write_all(&[min as i8 as u8, max as i8 as u8])?;
+		} else {
+			output.write_all(&min.to_le_bytes())?;
+			output.write_all(&max.to_le_bytes())?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Writes the JSON equivalent of the binary format, as read by peaks.js and wavesurfer.js
+pub(crate) fn write_json(output: &mut impl Write, header: Header, channels: &[&ChannelWriter]) -> Result<(), std::io::Error> {
+	write!(
+		output,
+		"{{\"version\":2,\"channels\":{},\"sample_rate\":{},\"samples_per_pixel\":{},\"bits\":{},\"length\":{},\"data\":[",
+		channels.len(),
+		header.sample_rate,
+		header.samples_per_pixel,
+		header.bits,
+		length(channels)
+	)?;
+
+	for (i, (min, max)) in pairs(&header, channels).enumerate() {
+		if i != 0 {
+			output.write_all(b",")?;
+		}
+
+		write!(output, "{min},{max}")?;
+	}
+
+	output.write_all(b"]}")
+}
+
+/// The number of points that every channel has
+fn length(channels: &[&ChannelWriter]) -> usize {
+	channels.iter().map(|channel| channel.as_points().len()).min().unwrap_or(0)
+}
+
+/// The quantized min/max pairs of each point, interleaved by channel
+fn pairs<'a>(header: &Header, channels: &'a [&ChannelWriter]) -> impl Iterator<Item = (i16, i16)> + 'a {
+	let max = if header.bits == 8 { i8::MAX as i16 } else { i16::MAX };
+
+	(0..length(channels)).flat_map(move |i| {
+		channels.iter().map(move |channel| {
+			let Point { min, max: peak, .. } = channel.as_points()[i];
+			(quantize(min, max), quantize(peak, max))
+		})
+	})
+}
+
+/// Maps a sample in the range of -1.0 to 1.0 to a signed integer in the range of `-max..=max`
+fn quantize(sample: f64, max: i16) -> i16 {
+	(sample * max as f64).round().clamp(-(max as f64), max as f64) as i16
+}
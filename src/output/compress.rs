@@ -0,0 +1,120 @@
+//! Compression of the output file
+
+use std::{io::Write, path::Path};
+
+#[cfg(feature = "compress")]
+use std::{fs::File, io::BufWriter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[strum(serialize_all = "lowercase")]
+/// Compression that can be applied to the output file
+pub enum Compression {
+	/// Gzip, as understood by `Content-Encoding: gzip`
+	Gzip,
+
+	/// Brotli, as understood by `Content-Encoding: br`
+	#[strum(to_string = "br", serialize = "brotli")]
+	Brotli,
+
+	/// Zstandard, as understood by `Content-Encoding: zstd`
+	Zstd,
+}
+impl Compression {
+	/// The extension that is appended to the default output file name
+	pub fn extension(self) -> &'static str {
+		match self {
+			Compression::Gzip => "gz",
+			Compression::Brotli => "br",
+			Compression::Zstd => "zst",
+		}
+	}
+}
+
+/// Brotli quality, from 0 to 11
+#[cfg(feature = "compress")]
+const BROTLI_QUALITY: u32 = 9;
+
+/// Brotli window size, as a power of two
+#[cfg(feature = "compress")]
+const BROTLI_WINDOW: u32 = 22;
+
+/// A [`Write`] that compresses everything written to it before passing it on
+#[cfg(feature = "compress")]
+pub(crate) enum Compressor<W: Write> {
+	Gzip(flate2::write::GzEncoder<W>),
+	Brotli(Box<brotli::CompressorWriter<W>>),
+	Zstd(zstd::Encoder<'static, W>),
+}
+#[cfg(feature = "compress")]
+impl<W: Write> Compressor<W> {
+	pub fn new(inner: W, compression: Compression) -> Result<Self, std::io::Error> {
+		Ok(match compression {
+			Compression::Gzip => Self::Gzip(flate2::write::GzEncoder::new(inner, flate2::Compression::default())),
+			Compression::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(inner, 0, BROTLI_QUALITY, BROTLI_WINDOW))),
+			Compression::Zstd => Self::Zstd(zstd::Encoder::new(inner, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+		})
+	}
+
+	/// Writes the end of the compressed stream, returning the inner writer
+	pub fn finish(self) -> Result<W, std::io::Error> {
+		match self {
+			Self::Gzip(inner) => inner.finish(),
+			Self::Brotli(inner) => Ok(inner.into_inner()),
+			Self::Zstd(inner) => inner.finish(),
+		}
+	}
+}
+#[cfg(feature = "compress")]
+impl<W: Write> Write for Compressor<W> {
+	fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+		match self {
+			Self::Gzip(inner) => inner.write(buf),
+			Self::Brotli(inner) => inner.write(buf),
+			Self::Zstd(inner) => inner.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> Result<(), std::io::Error> {
+		match self {
+			Self::Gzip(inner) => inner.flush(),
+			Self::Brotli(inner) => inner.flush(),
+			Self::Zstd(inner) => inner.flush(),
+		}
+	}
+}
+
+/// Passes `output` to `write`, through a [`Compressor`] if `compression` is set so that the output is compressed as it's written
+pub(crate) fn write_compressed<W: Write>(
+	mut output: W,
+	compression: Option<Compression>,
+	write: impl FnOnce(&mut dyn Write) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+	match compression {
+		#[cfg(feature = "compress")]
+		Some(compression) => {
+			let mut compressor = Compressor::new(output, compression)?;
+			write(&mut compressor)?;
+			compressor.finish()?.flush()
+		}
+
+		// Compression is rejected by the builder without the `compress` feature
+		_ => write(&mut output),
+	}
+}
+
+/// Streams the file at `source` through a [`Compressor`] into a new file at `destination`
+///
+/// For formats that aren't written in order, which are generated uncompressed before they're compressed.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_file(source: &Path, destination: &Path, compression: Compression) -> Result<(), std::io::Error> {
+	let mut compressor = Compressor::new(BufWriter::new(File::create(destination)?), compression)?;
+
+	std::io::copy(&mut File::open(source)?, &mut compressor)?;
+
+	compressor.finish()?.flush()
+}
+
+#[cfg(not(feature = "compress"))]
+pub(crate) fn compress_file(_source: &Path, _destination: &Path, _compression: Compression) -> Result<(), std::io::Error> {
+	unreachable!("compression is rejected by the builder without the `compress` feature")
+}
@@ -0,0 +1,330 @@
+//! Encoder for the document formats, which share the same logical structure of maps, arrays and scalars
+
+use super::JsonStyle;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EncoderKind {
+	Json,
+	MessagePack,
+	Cbor,
+}
+
+struct Container {
+	map: bool,
+	first: bool,
+
+	/// Whether the items of the container were written on their own lines, so its end needs to be too
+	multiline: bool,
+}
+
+/// Writes a document in any of the [`EncoderKind`]s
+///
+/// Maps and arrays are opened with their length, which is only used by the binary formats, and closed with [`Encoder::end`].
+/// Nothing is buffered, so documents of any size can be streamed to the output.
+pub(crate) struct Encoder<W: Write> {
+	inner: W,
+	kind: EncoderKind,
+	style: JsonStyle,
+	stack: Vec<Container>,
+}
+impl<W: Write> Encoder<W> {
+	pub fn new(inner: W, kind: EncoderKind, style: JsonStyle) -> Self {
+		Self {
+			inner,
+			kind,
+			style,
+			stack: Vec::new(),
+		}
+	}
+
+	pub fn kind(&self) -> EncoderKind {
+		self.kind
+	}
+
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	/// Writes the separator that comes before a value in an array
+	///
+	/// When pretty printing, `nested` maps and arrays are written on their own lines, and other values on the same line.
+	fn value(&mut self, nested: bool) -> Result<(), std::io::Error> {
+		if self.kind != EncoderKind::Json {
+			return Ok(());
+		}
+
+		let depth = self.stack.len();
+
+		if let Some(container) = self.stack.last_mut().filter(|container| !container.map) {
+			let first = std::mem::replace(&mut container.first, false);
+
+			if !first {
+				self.inner.write_all(b",")?;
+			}
+
+			if self.style == JsonStyle::Pretty {
+				if nested {
+					container.multiline = true;
+					write!(self.inner, "\n{:indent$}", "", indent = depth * 2)?;
+				} else if !first {
+					self.inner.write_all(b" ")?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Writes the head of a CBOR data item, or the equivalent MessagePack marker given its `fix` prefix and sized markers
+	fn head(&mut self, major: u8, fix: (u8, u64), sized: [u8; 4], len: u64) -> Result<(), std::io::Error> {
+		match self.kind {
+			EncoderKind::Json => Ok(()),
+
+			EncoderKind::MessagePack => {
+				let (prefix, max) = fix;
+				if len <= max {
+					self.inner.write_all(&[prefix | len as u8])
+				} else if sized[0] != 0 && len <= u8::MAX as u64 {
+					self.inner.write_all(&[sized[0], len as u8])
+				} else if len <= u16::MAX as u64 {
+					self.inner.write_all(&[sized[1]])?;
+					self.inner.write_all(&(len as u16).to_be_bytes())
+				} else if len <= u32::MAX as u64 || sized[3] == 0 {
+					self.inner.write_all(&[sized[2]])?;
+					self.inner.write_all(&(len as u32).to_be_bytes())
+				} else {
+					self.inner.write_all(&[sized[3]])?;
+					self.inner.write_all(&len.to_be_bytes())
+				}
+			}
+
+			EncoderKind::Cbor => {
+				let major = major << 5;
+				if len < 24 {
+					self.inner.write_all(&[major | len as u8])
+				} else if len <= u8::MAX as u64 {
+					self.inner.write_all(&[major | 24, len as u8])
+				} else if len <= u16::MAX as u64 {
+					self.inner.write_all(&[major | 25])?;
+					self.inner.write_all(&(len as u16).to_be_bytes())
+				} else if len <= u32::MAX as u64 {
+					self.inner.write_all(&[major | 26])?;
+					self.inner.write_all(&(len as u32).to_be_bytes())
+				} else {
+					self.inner.write_all(&[major | 27])?;
+					self.inner.write_all(&len.to_be_bytes())
+				}
+			}
+		}
+	}
+
+	pub fn map(&mut self, len: usize) -> Result<(), std::io::Error> {
+		self.value(true)?;
+		self.stack.push(Container {
+			map: true,
+			first: true,
+			multiline: false,
+		});
+
+		if self.kind == EncoderKind::Json {
+			self.inner.write_all(b"{")
+		} else {
+			self.head(5, (0x80, 15), [0, 0xde, 0xdf, 0], len as u64)
+		}
+	}
+
+	pub fn array(&mut self, len: usize) -> Result<(), std::io::Error> {
+		self.value(true)?;
+		self.stack.push(Container {
+			map: false,
+			first: true,
+			multiline: false,
+		});
+
+		if self.kind == EncoderKind::Json {
+			self.inner.write_all(b"[")
+		} else {
+			self.head(4, (0x90, 15), [0, 0xdc, 0xdd, 0], len as u64)
+		}
+	}
+
+	/// Closes the innermost map or array
+	pub fn end(&mut self) -> Result<(), std::io::Error> {
+		let container = self.stack.pop().expect("no map or array to end");
+
+		if self.kind != EncoderKind::Json {
+			return Ok(());
+		}
+
+		let depth = self.stack.len();
+		let own_line = match self.style {
+			JsonStyle::Compact => container.map && depth == 0,
+			JsonStyle::Pretty => container.multiline,
+			JsonStyle::Minified => false,
+		};
+
+		if own_line {
+			write!(self.inner, "\n{:indent$}", "", indent = depth * 2)?;
+		}
+
+		self.inner.write_all(if container.map { b"}" } else { b"]" })
+	}
+
+	pub fn key(&mut self, key: &str) -> Result<(), std::io::Error> {
+		if self.kind != EncoderKind::Json {
+			return self.str(key);
+		}
+
+		let depth = self.stack.len();
+		let container = self.stack.last_mut().filter(|container| container.map).expect("key outside of a map");
+
+		if !container.first {
+			self.inner.write_all(b",")?;
+		}
+		container.first = false;
+
+		match self.style {
+			// Keys of the outermost object are each written on their own line
+			JsonStyle::Compact if depth == 1 => self.inner.write_all(b"\n  ")?,
+
+			JsonStyle::Pretty => {
+				container.multiline = true;
+				write!(self.inner, "\n{:indent$}", "", indent = depth * 2)?;
+			}
+
+			_ => {}
+		}
+
+		self.json_str(key)?;
+
+		if self.style == JsonStyle::Pretty {
+			self.inner.write_all(b": ")
+		} else {
+			self.inner.write_all(b":")
+		}
+	}
+
+	pub fn str(&mut self, value: &str) -> Result<(), std::io::Error> {
+		self.value(false)?;
+
+		if self.kind == EncoderKind::Json {
+			self.json_str(value)
+		} else {
+			self.head(3, (0xa0, 31), [0xd9, 0xda, 0xdb, 0], value.len() as u64)?;
+			self.inner.write_all(value.as_bytes())
+		}
+	}
+
+	fn json_str(&mut self, value: &str) -> Result<(), std::io::Error> {
+		self.inner.write_all(b"\"")?;
+
+		let mut start = 0;
+		for (i, byte) in value.bytes().enumerate() {
+			let escape = match byte {
+				b'"' => "\\\"",
+				b'\\' => "\\\\",
+				b'\n' => "\\n",
+				b'\r' => "\\r",
+				b'\t' => "\\t",
+				0..=0x1f => "",
+				_ => continue,
+			};
+
+			self.inner.write_all(&value.as_bytes()[start..i])?;
+			start = i + 1;
+
+			if escape.is_empty() {
+				write!(self.inner, "\\u{byte:04x}")?;
+			} else {
+				self.inner.write_all(escape.as_bytes())?;
+			}
+		}
+
+		self.inner.write_all(&value.as_bytes()[start..])?;
+		self.inner.write_all(b"\"")
+	}
+
+	pub fn u64(&mut self, value: u64) -> Result<(), std::io::Error> {
+		self.value(false)?;
+
+		if self.kind == EncoderKind::Json {
+			write!(self.inner, "{value}")
+		} else {
+			self.head(0, (0x00, 127), [0xcc, 0xcd, 0xce, 0xcf], value)
+		}
+	}
+
+	/// Writes a float, or `null` if it is not finite
+	pub fn f64(&mut self, value: f64) -> Result<(), std::io::Error> {
+		self.value(false)?;
+
+		if !value.is_finite() {
+			return self.null();
+		}
+
+		match self.kind {
+			EncoderKind::Json => write!(self.inner, "{value}"),
+
+			EncoderKind::MessagePack => {
+				self.inner.write_all(&[0xcb])?;
+				self.inner.write_all(&value.to_be_bytes())
+			}
+
+			EncoderKind::Cbor => {
+				self.inner.write_all(&[0xfb])?;
+				self.inner.write_all(&value.to_be_bytes())
+			}
+		}
+	}
+
+	/// Writes a float with a fixed number of decimal places in JSON, or at full precision in the binary formats
+	pub fn decimal(&mut self, value: f64, precision: usize) -> Result<(), std::io::Error> {
+		if self.kind == EncoderKind::Json && value.is_finite() {
+			self.value(false)?;
+			write!(self.inner, "{value:.precision$}")
+		} else {
+			self.f64(value)
+		}
+	}
+
+	fn null(&mut self) -> Result<(), std::io::Error> {
+		match self.kind {
+			EncoderKind::Json => self.inner.write_all(b"null"),
+			EncoderKind::MessagePack => self.inner.write_all(&[0xc0]),
+			EncoderKind::Cbor => self.inner.write_all(&[0xf6]),
+		}
+	}
+}
+
+/// Writes a point as a fixed-width float into the array reserved for a channel
+///
+/// Every point takes up [`POINT_SIZE`] bytes, so that the space for the array can be reserved before the points are generated.
+pub(crate) fn write_point(output: &mut impl Write, kind: EncoderKind, point: f32) -> Result<(), std::io::Error> {
+	let marker = match kind {
+		EncoderKind::MessagePack => 0xca,
+		EncoderKind::Cbor => 0xfa,
+		EncoderKind::Json => unreachable!("points are written as text in JSON"),
+	};
+
+	output.write_all(&[marker])?;
+	output.write_all(&point.to_be_bytes())
+}
+
+/// The size in bytes of a point written by [`write_point`]
+pub(crate) const POINT_SIZE: u64 = 5;
+
+/// The size in bytes of the head of an array reserved by [`reserve_array`]
+pub(crate) const ARRAY_HEAD_SIZE: u64 = 5;
+
+/// Writes the head of an array of `len` items, always using a 32-bit length so that it can be rewritten in place once the number of points is known
+pub(crate) fn reserve_array(output: &mut impl Write, kind: EncoderKind, len: u32) -> Result<(), std::io::Error> {
+	let marker = match kind {
+		EncoderKind::MessagePack => 0xdd,
+		EncoderKind::Cbor => (4 << 5) | 26,
+		EncoderKind::Json => unreachable!("arrays are reserved with padding in JSON"),
+	};
+
+	output.write_all(&[marker])?;
+	output.write_all(&len.to_be_bytes())
+}
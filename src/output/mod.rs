@@ -0,0 +1,208 @@
+//! Output formats that the waveform can be written in
+
+use self::encoder::EncoderKind;
+use std::{
+	fs::File,
+	io::{Read, Seek, SeekFrom, Write},
+	ops::Range,
+};
+
+pub(crate) mod audiowaveform;
+pub(crate) mod compress;
+pub(crate) mod encoder;
+pub(crate) mod ndjson;
+pub(crate) mod raw;
+pub(crate) mod table;
+pub(crate) mod temp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[strum(serialize_all = "lowercase")]
+/// Formats that FFAudio2JSON can output
+pub enum OutputFormat {
+	#[default]
+	/// A JSON object with an array of points for each channel
+	Json,
+
+	/// The binary format used by [audiowaveform](https://github.com/bbc/audiowaveform)
+	///
+	/// Each point is stored as a pair of signed 8 or 16-bit minimum and maximum sample values.
+	Dat,
+
+	#[strum(serialize = "audiowaveform-json")]
+	/// The JSON format used by [audiowaveform](https://github.com/bbc/audiowaveform), as read by peaks.js and wavesurfer.js
+	///
+	/// Contains the same data as [`OutputFormat::Dat`], with the min/max pairs of each channel interleaved in a single `data` array.
+	AudiowaveformJson,
+
+	/// A small binary header followed by the points of each channel as little-endian `f32`s, for loading into a `Float32Array`
+	Float32,
+
+	/// A small binary header followed by the points of each channel as `u8`s in the range of 0 to 255, for loading into a `Uint8Array`
+	Uint8,
+
+	#[strum(to_string = "msgpack", serialize = "messagepack")]
+	/// The same document as [`OutputFormat::Json`], encoded as [MessagePack](https://msgpack.org), with the points stored as `float32`s
+	MessagePack,
+
+	/// The same document as [`OutputFormat::Json`], encoded as [CBOR](https://cbor.io), with the points stored as single-precision floats
+	Cbor,
+
+	/// Comma separated values, with a row for each point, a leading `time` column and a column for each channel
+	Csv,
+
+	/// Tab separated values, with a row for each point, a leading `time` column and a column for each channel
+	Tsv,
+
+	/// [Newline delimited JSON](https://github.com/ndjson/ndjson-spec), written as the points are generated
+	///
+	/// The first record is a `header` with the metadata of the audio, followed by `points` records holding up to
+	/// `chunk_size` points of a single channel and the index of their first point, and a `footer` with the duration and any analysis.
+	/// It's moved into place once it's complete like any other format, unless it's
+	/// [streamed in place](crate::FfAudio2JsonBuilder::stream_in_place) so that it can be read as it grows.
+	Ndjson,
+}
+impl OutputFormat {
+	/// The file extension that is used for this format by default
+	pub fn extension(self) -> &'static str {
+		match self {
+			OutputFormat::Json | OutputFormat::AudiowaveformJson => "json",
+			OutputFormat::Dat => "dat",
+			OutputFormat::Float32 | OutputFormat::Uint8 => "bin",
+			OutputFormat::MessagePack => "msgpack",
+			OutputFormat::Cbor => "cbor",
+			OutputFormat::Csv => "csv",
+			OutputFormat::Tsv => "tsv",
+			OutputFormat::Ndjson => "ndjson",
+		}
+	}
+
+	/// The MIME type of this format, e.g. for the `Content-Type` of an HTTP response
+	pub fn mime_type(self) -> &'static str {
+		match self {
+			OutputFormat::Json | OutputFormat::AudiowaveformJson => "application/json",
+			OutputFormat::Dat | OutputFormat::Float32 | OutputFormat::Uint8 => "application/octet-stream",
+			OutputFormat::MessagePack => "application/msgpack",
+			OutputFormat::Cbor => "application/cbor",
+			OutputFormat::Csv => "text/csv",
+			OutputFormat::Tsv => "text/tab-separated-values",
+			OutputFormat::Ndjson => "application/x-ndjson",
+		}
+	}
+
+	/// The encoder used by formats that share the structure of the JSON document
+	pub(crate) fn encoder_kind(self) -> Option<EncoderKind> {
+		match self {
+			OutputFormat::Json => Some(EncoderKind::Json),
+			OutputFormat::MessagePack => Some(EncoderKind::MessagePack),
+			OutputFormat::Cbor => Some(EncoderKind::Cbor),
+			OutputFormat::Dat
+			| OutputFormat::AudiowaveformJson
+			| OutputFormat::Float32
+			| OutputFormat::Uint8
+			| OutputFormat::Csv
+			| OutputFormat::Tsv
+			| OutputFormat::Ndjson => None,
+		}
+	}
+
+	/// Whether the output is written from start to end in one go, without seeking back to fill in space that was reserved
+	///
+	/// Only these formats can be compressed as they're written, the others are compressed once they have been generated.
+	pub(crate) fn is_sequential(self) -> bool {
+		match self {
+			OutputFormat::Dat | OutputFormat::AudiowaveformJson | OutputFormat::Csv | OutputFormat::Tsv => true,
+			OutputFormat::Json
+			| OutputFormat::MessagePack
+			| OutputFormat::Cbor
+			| OutputFormat::Float32
+			| OutputFormat::Uint8
+			| OutputFormat::Ndjson => false,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[strum(serialize_all = "lowercase")]
+/// How the points of each channel are written in the JSON output
+///
+/// Every encoding writes unsigned peaks. Signed minimum and maximum pairs are only written by the audiowaveform formats.
+pub enum PointEncoding {
+	#[default]
+	/// Decimal numbers in the range of 0.0 to 1.0, with `precision` digits
+	Decimal,
+
+	/// Integers in the range of 0 to 255 (8-bit) or 0 to 65535 (16-bit)
+	Integer,
+
+	/// A base64 string of the same integers, stored as bytes (8-bit) or little-endian `u16`s (16-bit)
+	Base64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[strum(serialize_all = "lowercase")]
+/// How the JSON output is laid out
+pub enum JsonStyle {
+	#[default]
+	/// Each key of the outermost object on its own line, with no other whitespace
+	Compact,
+
+	/// Indented by two spaces, with every key and every nested object on its own line
+	///
+	/// Arrays of numbers are kept on a single line.
+	Pretty,
+
+	/// No whitespace at all
+	Minified,
+}
+
+/// The separator between the points of a channel in the JSON output
+pub(crate) fn separator(style: JsonStyle) -> &'static str {
+	if style == JsonStyle::Pretty {
+		", "
+	} else {
+		","
+	}
+}
+
+/// The largest integer that a point is mapped to when quantized to `bits` bits
+pub(crate) fn quantize_scale(bits: u8) -> u32 {
+	(1 << bits) - 1
+}
+
+/// Maps a point in the range of 0.0 to 1.0 to an integer in the range of `0..=quantize_scale(bits)`
+pub(crate) fn quantize(sample: f64, bits: u8) -> u32 {
+	let scale = quantize_scale(bits);
+	((sample * scale as f64).round() as u32).min(scale)
+}
+
+/// Moves each block of the file back so that they follow on from each other, starting at the first block,
+/// removing the unused space that was reserved between them.
+///
+/// Returns the offset of the end of the last block.
+pub(crate) fn compact(file: &mut File, blocks: &[Range<u64>]) -> Result<u64, std::io::Error> {
+	let mut buffer = [0; 8192];
+	let mut end = blocks.first().map(|block| block.start).unwrap_or(0);
+
+	for block in blocks {
+		if block.start == end {
+			end = block.end;
+			continue;
+		}
+
+		let mut position = block.start;
+		while position < block.end {
+			let len = (block.end - position).min(buffer.len() as u64) as usize;
+
+			file.seek(SeekFrom::Start(position))?;
+			file.read_exact(&mut buffer[..len])?;
+
+			file.seek(SeekFrom::Start(end))?;
+			file.write_all(&buffer[..len])?;
+
+			position += len as u64;
+			end += len as u64;
+		}
+	}
+
+	Ok(end)
+}
@@ -0,0 +1,176 @@
+//! Records of the newline delimited JSON format
+//!
+//! Every record is a minified JSON object on its own line, with a `type` key saying what kind of record it is.
+
+use super::{
+	encoder::{Encoder, EncoderKind},
+	JsonStyle, PointEncoding,
+};
+use crate::{channels::Channel, FfAudio2Json};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{fs::File, io::Write};
+
+/// Starts a record, which is ended with [`end`]
+pub(crate) fn start(encoder: &mut Encoder<impl Write>, kind: &str) -> Result<(), std::io::Error> {
+	encoder.map(0)?;
+	encoder.key("type")?;
+	encoder.str(kind)
+}
+
+/// Ends a record started with [`start`]
+pub(crate) fn end(encoder: &mut Encoder<impl Write>) -> Result<(), std::io::Error> {
+	encoder.end()?;
+	encoder.get_mut().write_all(b"\n")
+}
+
+/// The metadata of the audio, written before any points
+pub(crate) struct Header {
+	pub sample_rate: u32,
+	pub samples_per_point: u64,
+
+	/// The number of points that each channel is expected to have
+	pub points: u64,
+
+	pub duration: f64,
+}
+impl Header {
+	pub fn write(&self, encoder: &mut Encoder<impl Write>, config: &FfAudio2Json, generator: &str) -> Result<(), std::io::Error> {
+		start(encoder, "header")?;
+
+		if !config.no_header {
+			encoder.key("_generator")?;
+			encoder.str(generator)?;
+		}
+
+		encoder.key("channels")?;
+		encoder.array(config.channels.len())?;
+		for channel in &config.channels {
+			encoder.str(&channel.to_string())?;
+		}
+		encoder.end()?;
+
+		encoder.key("sample_rate")?;
+		encoder.u64(self.sample_rate as u64)?;
+		encoder.key("samples_per_point")?;
+		encoder.u64(self.samples_per_point)?;
+		encoder.key("points")?;
+		encoder.u64(self.points)?;
+
+		if config.encoding != PointEncoding::Decimal {
+			encoder.key("scale")?;
+			encoder.u64(super::quantize_scale(config.bits) as u64)?;
+		}
+
+		encoder.key("duration")?;
+		encoder.f64(self.duration)?;
+
+		end(encoder)
+	}
+}
+
+/// Collects the points of a channel into `points` records of up to `chunk_size` points each
+///
+/// Each record is appended to the output with a single write as soon as it is full, so that it can be read while the rest of the points are generated.
+pub(crate) struct PointRecords {
+	/// The output file, opened for appending
+	file: File,
+
+	channel: Channel,
+
+	/// The index of the first point of the current record
+	start: usize,
+
+	/// The number of points in the current record
+	len: usize,
+
+	/// The encoded points of the current record, or the quantized bytes if they are encoded as base64
+	points: Vec<u8>,
+
+	encoding: PointEncoding,
+	chunk_size: usize,
+}
+impl PointRecords {
+	pub fn new(file: File, channel: Channel, config: &FfAudio2Json) -> Self {
+		Self {
+			file,
+			channel,
+			start: 0,
+			len: 0,
+			points: Vec::new(),
+			encoding: config.encoding,
+			chunk_size: config.chunk_size as usize,
+		}
+	}
+
+	/// Adds a point that has already been scaled to the output
+	pub fn push(&mut self, sample: f64, config: &FfAudio2Json) -> Result<(), std::io::Error> {
+		match self.encoding {
+			PointEncoding::Decimal => {
+				if self.len != 0 {
+					self.points.push(b',');
+				}
+				write!(self.points, "{sample:.precision$}", precision = config.precision)?;
+			}
+
+			PointEncoding::Integer => {
+				if self.len != 0 {
+					self.points.push(b',');
+				}
+				write!(self.points, "{}", super::quantize(sample, config.bits))?;
+			}
+
+			PointEncoding::Base64 => {
+				let sample = super::quantize(sample, config.bits);
+
+				if config.bits == 8 {
+					self.points.push(sample as u8);
+				} else {
+					self.points.extend_from_slice(&(sample as u16).to_le_bytes());
+				}
+			}
+		}
+
+		self.len += 1;
+
+		if self.len >= self.chunk_size {
+			self.flush()?;
+		}
+
+		Ok(())
+	}
+
+	/// Writes the current record, if it has any points
+	pub fn flush(&mut self) -> Result<(), std::io::Error> {
+		if self.len == 0 {
+			return Ok(());
+		}
+
+		let mut record = Encoder::new(Vec::new(), EncoderKind::Json, JsonStyle::Minified);
+
+		start(&mut record, "points")?;
+		record.key("channel")?;
+		record.str(&self.channel.to_string())?;
+		record.key("start")?;
+		record.u64(self.start as u64)?;
+		record.key("points")?;
+
+		if self.encoding == PointEncoding::Base64 {
+			record.str(&STANDARD.encode(&self.points))?;
+		} else {
+			let record = record.get_mut();
+			record.push(b'[');
+			record.extend_from_slice(&self.points);
+			record.push(b']');
+		}
+
+		end(&mut record)?;
+
+		self.file.write_all(record.get_mut())?;
+
+		self.start += self.len;
+		self.len = 0;
+		self.points.clear();
+
+		Ok(())
+	}
+}
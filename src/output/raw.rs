@@ -0,0 +1,112 @@
+//! Encoder for the raw binary format, which can be loaded straight into a `Float32Array` or `Uint8Array`
+//!
+//! | Offset | Type      | Description                                                   |
+//! |--------|-----------|---------------------------------------------------------------|
+//! | 0      | `[u8; 4]` | Magic bytes, `FA2W`                                           |
+//! | 4      | `u16`     | Version, currently `1`                                        |
+//! | 6      | `u8`      | Type of the points, `0` for `f32` or `1` for `u8`             |
+//! | 7      | `u8`      | Number of channels                                            |
+//! | 8      | `u32`     | Number of points in each channel                              |
+//! | 12     | `u32`     | Sample rate of the audio stream                               |
+//! | 16     | `f64`     | Duration of the points in seconds                             |
+//! | 24     | `f64`     | Time in the audio stream where the points start, in seconds   |
+//! | 32     | `f64`     | Time in the audio stream where the points end, in seconds     |
+//! | 40     | `u16`     | Length of the channel list                                    |
+//! | 42     | `[u8]`    | Comma separated names of the channels, e.g. `left,right`      |
+//!
+//! The points cover the whole stream unless silence was trimmed, in which case they only cover `start..end`.
+//! The header is padded with zeroes to a multiple of 8 bytes, and followed by the points of each channel in turn.
+//! All values are little-endian.
+
+use crate::{
+	channels::Channel,
+	output::{self, OutputFormat},
+};
+use std::{
+	fs::File,
+	io::{Seek, SeekFrom, Write},
+};
+
+const MAGIC: [u8; 4] = *b"FA2W";
+const VERSION: u16 = 1;
+
+/// Offset of the number of points in the header
+const POINTS_OFFSET: u64 = 8;
+
+/// Length of the header before the channel list
+const FIXED_SIZE: u64 = 42;
+
+pub(crate) struct Header<'a> {
+	pub format: OutputFormat,
+	pub channels: &'a [Channel],
+	pub points: u32,
+	pub sample_rate: u32,
+
+	/// The duration of the points, which is shorter than the stream if silence was trimmed
+	pub duration: f64,
+
+	/// Where the points start in the stream, in seconds
+	pub start: f64,
+}
+impl Header<'_> {
+	fn channel_list(&self) -> String {
+		self.channels.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+	}
+
+	/// The length of the header, including padding, which is where the points start
+	pub fn size(&self) -> u64 {
+		(FIXED_SIZE + self.channel_list().len() as u64).next_multiple_of(8)
+	}
+
+	/// The offset of the points of the `n`th channel
+	pub fn channel_offset(&self, n: usize) -> u64 {
+		self.size() + n as u64 * self.points as u64 * element_size(self.format)
+	}
+
+	pub fn write(&self, output: &mut impl Write) -> Result<(), std::io::Error> {
+		let channel_list = self.channel_list();
+
+		output.write_all(&MAGIC)?;
+		output.write_all(&VERSION.to_le_bytes())?;
+		output.write_all(&[if self.format == OutputFormat::Uint8 { 1 } else { 0 }, self.channels.len() as u8])?;
+		output.write_all(&self.points.to_le_bytes())?;
+		output.write_all(&self.sample_rate.to_le_bytes())?;
+		output.write_all(&self.duration.to_le_bytes())?;
+		output.write_all(&self.start.to_le_bytes())?;
+		output.write_all(&(self.start + self.duration).to_le_bytes())?;
+		output.write_all(&(channel_list.len() as u16).to_le_bytes())?;
+		output.write_all(channel_list.as_bytes())?;
+
+		let padding = self.size() - FIXED_SIZE - channel_list.len() as u64;
+		output.write_all(&vec![0; padding as usize])
+	}
+}
+
+/// The size in bytes of a single point
+pub(crate) fn element_size(format: OutputFormat) -> u64 {
+	if format == OutputFormat::Uint8 {
+		1
+	} else {
+		4
+	}
+}
+
+/// Moves the points of each channel next to each other if fewer points were generated than space was reserved for,
+/// and updates the number of points in the header.
+pub(crate) fn finish(file: &mut File, header: &Header, points: usize) -> Result<(), std::io::Error> {
+	if points as u32 != header.points {
+		let size = element_size(header.format);
+
+		let blocks = (0..header.channels.len())
+			.map(|n| header.channel_offset(n)..header.channel_offset(n) + points as u64 * size)
+			.collect::<Vec<_>>();
+
+		let end = output::compact(file, &blocks)?;
+		file.set_len(end)?;
+
+		file.seek(SeekFrom::Start(POINTS_OFFSET))?;
+		file.write_all(&(points as u32).to_le_bytes())?;
+	}
+
+	file.flush()
+}
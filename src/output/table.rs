@@ -0,0 +1,46 @@
+//! Encoder for delimited text tables, such as CSV and TSV
+
+use crate::{
+	channels::{self, Channel, ChannelWriter},
+	FfAudio2Json,
+};
+use std::io::Write;
+
+pub(crate) struct Timeline {
+	pub sample_rate: u32,
+	pub samples_per_point: u64,
+
+	/// The sample that the first point starts at
+	pub start: u64,
+}
+
+/// Writes a row for each point, with a leading `time` column containing the start of the point in seconds, and a column for each channel
+pub(crate) fn write(
+	output: &mut impl Write,
+	delimiter: char,
+	timeline: Timeline,
+	channels: &[(Channel, &ChannelWriter)],
+	config: &FfAudio2Json,
+) -> Result<(), std::io::Error> {
+	write!(output, "time")?;
+	for (channel, _) in channels {
+		write!(output, "{delimiter}{channel}")?;
+	}
+	writeln!(output)?;
+
+	let length = channels.iter().map(|(_, writer)| writer.as_points().len()).min().unwrap_or(0);
+
+	for i in 0..length {
+		let time = (timeline.start + i as u64 * timeline.samples_per_point) as f64 / timeline.sample_rate as f64;
+		write!(output, "{time:.6}")?;
+
+		for (_, writer) in channels {
+			let sample = channels::scale_peak(writer.as_points()[i].peak, config);
+			write!(output, "{delimiter}{sample:.precision$}", precision = config.precision)?;
+		}
+
+		writeln!(output)?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,106 @@
+//! Temporary files that output is generated in before it is moved into place
+
+use std::{
+	fs::OpenOptions,
+	io::ErrorKind,
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicU32, Ordering},
+};
+
+/// Distinguishes the temporary files of concurrent runs in the same process
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// An empty file next to the output, which is removed when dropped unless it has been [persisted](TempFile::persist)
+pub(crate) struct TempFile {
+	path: Option<PathBuf>,
+
+	/// Whether the file is the output itself, rather than a file that is moved into its place
+	in_place: bool,
+}
+impl TempFile {
+	/// Creates a hidden file in the same directory as `destination`, so that it can be renamed over it atomically
+	pub fn create_for(destination: &Path) -> Result<Self, std::io::Error> {
+		let name = destination.file_name().unwrap_or_default().to_string_lossy();
+
+		loop {
+			let path = destination.with_file_name(format!(".{name}.{}.{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+			match OpenOptions::new().write(true).create_new(true).open(&path) {
+				Ok(_) => {
+					return Ok(Self {
+						path: Some(path),
+						in_place: false,
+					})
+				}
+
+				// Left behind by a process that had the same ID
+				Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+
+				Err(err) => return Err(err),
+			}
+		}
+	}
+
+	/// Creates the output at `destination` itself, for output that is [streamed in place](crate::FfAudio2JsonBuilder::stream_in_place)
+	///
+	/// The output isn't replaced atomically, but it's still removed when dropped so that nothing incomplete is left behind.
+	/// Unless `overwrite` is set, this fails with [`ErrorKind::AlreadyExists`] if there is already a file at `destination`.
+	pub fn create_in_place(destination: &Path, overwrite: bool) -> Result<Self, std::io::Error> {
+		OpenOptions::new()
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.create_new(!overwrite)
+			.open(destination)?;
+
+		Ok(Self {
+			path: Some(destination.to_path_buf()),
+			in_place: true,
+		})
+	}
+
+	pub fn path(&self) -> &Path {
+		self.path.as_deref().unwrap()
+	}
+
+	/// Moves the file to `destination`, or keeps it if it was [created in place](TempFile::create_in_place)
+	///
+	/// Unless `overwrite` is set, this fails with [`ErrorKind::AlreadyExists`] if there is already a file at `destination`.
+	pub fn persist(mut self, destination: &Path, overwrite: bool) -> Result<(), std::io::Error> {
+		// The content must reach the disk before the file is moved into place, or a crash could leave an empty output behind
+		OpenOptions::new().write(true).open(self.path())?.sync_all()?;
+
+		if self.in_place {
+			self.path = None;
+		} else if overwrite {
+			std::fs::rename(self.path(), destination)?;
+			self.path = None;
+		} else {
+			// Unlike renaming, linking never replaces the destination. The temporary file is then removed on drop.
+			match std::fs::hard_link(self.path(), destination) {
+				Ok(()) => {}
+				Err(err) if err.kind() == ErrorKind::AlreadyExists => return Err(err),
+
+				// Some filesystems don't support hard links, so the destination is claimed by creating it before the file is renamed over it
+				Err(_) => {
+					OpenOptions::new().write(true).create_new(true).open(destination)?;
+
+					if let Err(err) = std::fs::rename(self.path(), destination) {
+						std::fs::remove_file(destination).ok();
+						return Err(err);
+					}
+					self.path = None;
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+impl Drop for TempFile {
+	fn drop(&mut self) {
+		if let Some(path) = self.path.take() {
+			std::fs::remove_file(path).ok();
+		}
+	}
+}
@@ -0,0 +1,38 @@
+//! Reporting of the progress of generating a waveform
+
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+/// How far through the audio stream the generator is
+pub struct Progress {
+	/// The timestamp in seconds of the audio that has been decoded so far
+	pub time: f64,
+
+	/// The fraction of the audio stream that has been decoded, in the range of 0.0 to 1.0
+	pub fraction: f64,
+
+	/// The number of points that have been written to each channel so far
+	pub points: usize,
+
+	/// The number of bytes of the input file that have been read so far
+	pub bytes_read: u64,
+}
+
+/// A callback that is invoked with the [`Progress`] of the generator
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+impl ProgressCallback {
+	pub fn new(callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+		Self(Arc::new(callback))
+	}
+
+	pub fn report(&self, progress: &Progress) {
+		(self.0)(progress)
+	}
+}
+impl std::fmt::Debug for ProgressCallback {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("ProgressCallback")
+	}
+}
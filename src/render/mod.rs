@@ -0,0 +1,393 @@
+//! Rendering of generated waveforms to PNG and SVG images
+//!
+//! Each channel of the [`Waveform`] is drawn in its own lane, stacked from top to bottom in the order that the channels were requested.
+
+use crate::{Error, Waveform};
+use std::{
+	fs::File,
+	io::{BufWriter, Write},
+	path::Path,
+	str::FromStr,
+};
+
+mod png;
+mod svg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[strum(serialize_all = "lowercase")]
+/// How the points of a channel are drawn
+pub enum RenderStyle {
+	/// A vertical bar for each point, separated by the [`gap`](RendererBuilder::gap)
+	#[default]
+	Bars,
+
+	/// A line through the points
+	Line,
+
+	/// The area under a line through the points
+	Filled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A colour with an alpha channel
+pub struct Color {
+	/// Red component
+	pub r: u8,
+
+	/// Green component
+	pub g: u8,
+
+	/// Blue component
+	pub b: u8,
+
+	/// Alpha component, where 0 is fully transparent and 255 is fully opaque
+	pub a: u8,
+}
+impl Color {
+	/// Creates an opaque colour
+	pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+		Self { r, g, b, a: u8::MAX }
+	}
+
+	/// Creates a colour with the given alpha
+	pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+		Self { r, g, b, a }
+	}
+}
+impl FromStr for Color {
+	type Err = ParseColorError;
+
+	/// Parses a colour in the form of `#rrggbb` or `#rrggbbaa`, where the `#` is optional
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let hex = s.strip_prefix('#').unwrap_or(s);
+
+		if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+			return Err(ParseColorError(s.to_string()));
+		}
+
+		let component = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+
+		Ok(Self {
+			r: component(0),
+			g: component(1),
+			b: component(2),
+			a: if hex.len() == 8 { component(3) } else { u8::MAX },
+		})
+	}
+}
+impl std::fmt::Display for Color {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+
+		if self.a != u8::MAX {
+			write!(f, "{:02x}", self.a)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Invalid colour {0:?}, expected #rrggbb or #rrggbbaa")]
+/// Error returned when parsing a [`Color`] fails
+pub struct ParseColorError(String);
+
+const DEFAULT_WIDTH: u32 = 1800;
+const DEFAULT_HEIGHT: u32 = 280;
+const DEFAULT_DPI: f64 = 96.0;
+
+/// Largest number of pixels in a PNG image, which is rendered in memory with 4 bytes per pixel
+const MAX_PIXELS: f64 = (1 << 26) as f64;
+
+/// The colours that channels are drawn in by default
+const DEFAULT_COLORS: [Color; 6] = [
+	Color::rgb(0x1f, 0x77, 0xb4),
+	Color::rgb(0xff, 0x7f, 0x0e),
+	Color::rgb(0x2c, 0xa0, 0x2c),
+	Color::rgb(0xd6, 0x27, 0x28),
+	Color::rgb(0x94, 0x67, 0xbd),
+	Color::rgb(0x8c, 0x56, 0x4b),
+];
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+/// Configuration for rendering a [`Waveform`] to an image
+pub struct Renderer {
+	/// Width of the image, in pixels at 96 DPI
+	#[builder(default = "DEFAULT_WIDTH")]
+	pub(crate) width: u32,
+
+	/// Height of the image, in pixels at 96 DPI
+	#[builder(default = "DEFAULT_HEIGHT")]
+	pub(crate) height: u32,
+
+	/// How the points of each channel are drawn
+	#[builder(default)]
+	pub(crate) style: RenderStyle,
+
+	/// Draw each channel symmetrically around its centre line, rather than upwards from the bottom of its lane
+	#[builder(default = "true")]
+	pub(crate) mirrored: bool,
+
+	/// Colour of the background, which is transparent if not set
+	#[builder(default = "None")]
+	pub(crate) background: Option<Color>,
+
+	/// Colours of the channels, in the order that the channels were requested
+	///
+	/// If there are more channels than colours, the colours are repeated.
+	#[builder(default = "DEFAULT_COLORS.to_vec()")]
+	pub(crate) colors: Vec<Color>,
+
+	/// Width of each bar in the [`Bars`](RenderStyle::Bars) style, and the thickness of the line in the [`Line`](RenderStyle::Line) style
+	///
+	/// Bars are widened to fill the image if there are not enough points for bars of this width.
+	#[builder(default = "2")]
+	pub(crate) bar_width: u32,
+
+	/// Space between bars and between the lanes of the channels
+	#[builder(default = "1")]
+	pub(crate) gap: u32,
+
+	/// Resolution of PNG images, which scales the image and is recorded in its metadata
+	///
+	/// SVG images are resolution independent, so this has no effect on them.
+	#[builder(default = "DEFAULT_DPI")]
+	pub(crate) dpi: f64,
+}
+impl Renderer {
+	/// Creates a new builder for [`Renderer`]
+	pub fn builder() -> RendererBuilder {
+		RendererBuilder::default()
+	}
+
+	/// Renders the waveform to a file, as an SVG image if the path has an `svg` extension or as a PNG image otherwise
+	pub fn render(&self, waveform: &Waveform, path: impl AsRef<Path>) -> Result<(), Error> {
+		let path = path.as_ref();
+		let mut output = BufWriter::new(File::create(path)?);
+
+		if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) {
+			self.render_svg(waveform, &mut output)?;
+		} else {
+			self.render_png(waveform, &mut output)?;
+		}
+
+		output.flush()?;
+
+		Ok(())
+	}
+
+	/// Renders the waveform as a PNG image
+	pub fn render_png(&self, waveform: &Waveform, output: impl Write) -> Result<(), Error> {
+		png::render(self, waveform, output)
+	}
+
+	/// Renders the waveform as an SVG image
+	pub fn render_svg(&self, waveform: &Waveform, output: impl Write) -> Result<(), Error> {
+		Ok(svg::render(self, waveform, output)?)
+	}
+
+	/// Lays out the shapes of each channel in an image `scale` times the configured size
+	pub(crate) fn layout(&self, waveform: &Waveform, scale: f64) -> Vec<(Color, Vec<Shape>)> {
+		let width = (self.width as f64 * scale).round().max(1.0);
+		let height = (self.height as f64 * scale).round().max(1.0);
+		let gap = (self.gap as f64 * scale).round();
+		let thickness = (self.bar_width as f64 * scale).round().max(1.0);
+
+		let lanes = waveform.channels.len();
+		let lane_height = ((height - gap * lanes.saturating_sub(1) as f64) / lanes.max(1) as f64).max(1.0);
+
+		waveform
+			.channels
+			.iter()
+			.enumerate()
+			.map(|(index, (_, points))| {
+				let lane = Lane {
+					top: index as f64 * (lane_height + gap),
+					height: lane_height,
+					mirrored: self.mirrored,
+				};
+
+				let shapes = match self.style {
+					RenderStyle::Bars => {
+						let slots = (((width + gap) / (thickness + gap)) as usize).clamp(1, points.len().max(1));
+						let pitch = width / slots as f64;
+						let bar_width = (pitch - gap).max(1.0);
+
+						resample(points, slots)
+							.into_iter()
+							.enumerate()
+							.filter(|(_, value)| *value > 0.0)
+							.map(|(i, value)| {
+								let (top, bottom) = lane.span(value);
+								Shape::Rect {
+									x: i as f64 * pitch,
+									y: top,
+									width: bar_width,
+									height: bottom - top,
+								}
+							})
+							.collect()
+					}
+
+					RenderStyle::Filled => vec![Shape::Area(
+						resample(points, width as usize)
+							.into_iter()
+							.enumerate()
+							.map(|(x, value)| {
+								let (top, bottom) = lane.span(value);
+								(x as f64, top, bottom)
+							})
+							.collect(),
+					)],
+
+					RenderStyle::Line => {
+						let values = resample(points, width as usize);
+						let line = |edge: fn((f64, f64)) -> f64| Shape::Line {
+							points: values
+								.iter()
+								.enumerate()
+								.map(|(x, value)| (x as f64 + 0.5, edge(lane.span(*value))))
+								.collect(),
+							thickness,
+						};
+
+						if self.mirrored {
+							vec![line(|(top, _)| top), line(|(_, bottom)| bottom)]
+						} else {
+							vec![line(|(top, _)| top)]
+						}
+					}
+				};
+
+				(self.colors[index % self.colors.len()], shapes)
+			})
+			.collect()
+	}
+}
+
+impl RendererBuilder {
+	fn validate(&self) -> Result<(), String> {
+		if self.width == Some(0) || self.height == Some(0) {
+			return Err("Image width and height must not be zero".to_string());
+		}
+
+		if self.bar_width == Some(0) {
+			return Err("Bar width must not be zero".to_string());
+		}
+
+		if self.dpi.is_some_and(|dpi| !dpi.is_finite() || dpi <= 0.0) {
+			return Err("DPI must be positive".to_string());
+		}
+
+		let (width, height, dpi) = (
+			self.width.unwrap_or(DEFAULT_WIDTH),
+			self.height.unwrap_or(DEFAULT_HEIGHT),
+			self.dpi.unwrap_or(DEFAULT_DPI),
+		);
+		if png_size(width, height, dpi).is_none() {
+			return Err(format!(
+				"A {width}x{height} image at {dpi} DPI is too large to render, it must have at most {MAX_PIXELS} pixels"
+			));
+		}
+
+		if self.colors.as_ref().is_some_and(|colors| colors.is_empty()) {
+			return Err("At least one colour must be specified".to_string());
+		}
+
+		Ok(())
+	}
+}
+
+/// The size in pixels of a PNG image that is `width` by `height` at 96 DPI, or `None` if it's too large to render
+pub(crate) fn png_size(width: u32, height: u32, dpi: f64) -> Option<(u32, u32)> {
+	let scale = dpi / 96.0;
+	let width = (width as f64 * scale).round().max(1.0);
+	let height = (height as f64 * scale).round().max(1.0);
+
+	(width * height <= MAX_PIXELS).then_some((width as u32, height as u32))
+}
+
+/// A shape in the image, in pixels
+pub(crate) enum Shape {
+	Rect {
+		x: f64,
+		y: f64,
+		width: f64,
+		height: f64,
+	},
+
+	/// A one pixel wide column for each `(x, top, bottom)`
+	Area(Vec<(f64, f64, f64)>),
+
+	Line {
+		points: Vec<(f64, f64)>,
+		thickness: f64,
+	},
+}
+
+/// The region of the image that a channel is drawn in
+struct Lane {
+	top: f64,
+	height: f64,
+	mirrored: bool,
+}
+impl Lane {
+	/// The top and bottom of a value in the lane
+	fn span(&self, value: f64) -> (f64, f64) {
+		let value = value.clamp(0.0, 1.0);
+
+		if self.mirrored {
+			let centre = self.top + self.height / 2.0;
+			(centre - value * self.height / 2.0, centre + value * self.height / 2.0)
+		} else {
+			let bottom = self.top + self.height;
+			(bottom - value * self.height, bottom)
+		}
+	}
+}
+
+/// Resamples the points to `len` values, taking the maximum of the points that fall into each value, or interpolating if there are fewer points than values
+fn resample(points: &[f64], len: usize) -> Vec<f64> {
+	if points.is_empty() || len == 0 {
+		return Vec::new();
+	}
+
+	if points.len() >= len {
+		(0..len)
+			.map(|i| {
+				let start = i * points.len() / len;
+				let end = ((i + 1) * points.len() / len).max(start + 1);
+				points[start..end].iter().copied().fold(0.0, f64::max)
+			})
+			.collect()
+	} else if len == 1 || points.len() == 1 {
+		vec![points[0]; len]
+	} else {
+		(0..len)
+			.map(|i| {
+				let position = i as f64 * (points.len() - 1) as f64 / (len - 1) as f64;
+				let index = (position as usize).min(points.len() - 2);
+				let t = position - index as f64;
+				points[index] * (1.0 - t) + points[index + 1] * t
+			})
+			.collect()
+	}
+}
+
+#[test]
+fn test_resample() {
+	assert_eq!(resample(&[0.1, 0.5, 0.2, 0.4], 2), vec![0.5, 0.4]);
+	assert_eq!(resample(&[0.0, 1.0], 3), vec![0.0, 0.5, 1.0]);
+	assert_eq!(resample(&[0.3], 2), vec![0.3, 0.3]);
+	assert!(resample(&[], 4).is_empty());
+}
+
+#[test]
+fn test_color() {
+	assert_eq!("#ff8000".parse::<Color>().unwrap(), Color::rgb(0xff, 0x80, 0x00));
+	assert_eq!("ff800080".parse::<Color>().unwrap(), Color::rgba(0xff, 0x80, 0x00, 0x80));
+	assert_eq!(Color::rgba(0xff, 0x80, 0x00, 0x80).to_string(), "#ff800080");
+	assert!("#ff80".parse::<Color>().is_err());
+	assert!("#gg8000".parse::<Color>().is_err());
+}
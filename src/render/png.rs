@@ -0,0 +1,125 @@
+//! Rasterises the [`Shape`]s of a waveform and encodes them as a PNG image
+
+use super::{Color, Renderer, Shape};
+use crate::{Error, Waveform};
+use std::io::Write;
+
+const INCHES_PER_METRE: f64 = 39.3701;
+
+/// An RGBA image that shapes are blended onto
+struct Raster {
+	width: usize,
+	height: usize,
+	pixels: Vec<u8>,
+}
+impl Raster {
+	/// Creates a raster filled with `background`, or `None` if its size in bytes would overflow
+	fn new(width: usize, height: usize, background: Option<Color>) -> Option<Self> {
+		let background = background.unwrap_or(Color::rgba(0, 0, 0, 0));
+		width.checked_mul(height)?.checked_mul(4)?;
+
+		Some(Self {
+			width,
+			height,
+			pixels: [background.r, background.g, background.b, background.a].repeat(width * height),
+		})
+	}
+
+	/// Blends a colour onto the pixels covered by a rectangle, which always covers at least one pixel in each direction
+	fn fill(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: Color) {
+		let clamp = |value: f64, max: usize| (value.round().max(0.0) as usize).min(max);
+
+		let (x0, mut x1) = (clamp(x0, self.width), clamp(x1, self.width));
+		let (y0, mut y1) = (clamp(y0, self.height), clamp(y1, self.height));
+
+		if x1 == x0 && x0 < self.width {
+			x1 += 1;
+		}
+		if y1 == y0 && y0 < self.height {
+			y1 += 1;
+		}
+
+		for y in y0..y1 {
+			for x in x0..x1 {
+				let i = (y * self.width + x) * 4;
+				blend(&mut self.pixels[i..i + 4], color);
+			}
+		}
+	}
+
+	fn draw(&mut self, shape: &Shape, color: Color) {
+		match shape {
+			Shape::Rect { x, y, width, height } => self.fill(*x, *y, x + width, y + height, color),
+
+			Shape::Area(columns) => {
+				for (x, top, bottom) in columns {
+					if bottom > top {
+						self.fill(*x, *top, x + 1.0, *bottom, color);
+					}
+				}
+			}
+
+			Shape::Line { points, thickness } => {
+				// Each column is joined to the next with a vertical span, so that steep lines have no holes in them
+				for (i, (x, y)) in points.iter().enumerate() {
+					let next = points.get(i + 1).map_or(*y, |(_, next)| *next);
+					let (top, bottom) = (y.min(next), y.max(next));
+
+					self.fill(
+						x - thickness / 2.0,
+						top - thickness / 2.0,
+						x + thickness / 2.0,
+						bottom + thickness / 2.0,
+						color,
+					);
+				}
+			}
+		}
+	}
+}
+
+/// Blends a colour over an RGBA pixel
+fn blend(pixel: &mut [u8], color: Color) {
+	let alpha = color.a as f64 / 255.0;
+	let below = pixel[3] as f64 / 255.0 * (1.0 - alpha);
+	let out = alpha + below;
+
+	if out <= 0.0 {
+		return;
+	}
+
+	for (channel, value) in pixel[..3].iter_mut().zip([color.r, color.g, color.b]) {
+		*channel = ((value as f64 * alpha + *channel as f64 * below) / out).round() as u8;
+	}
+	pixel[3] = (out * 255.0).round() as u8;
+}
+
+pub(super) fn render(renderer: &Renderer, waveform: &Waveform, output: impl Write) -> Result<(), Error> {
+	let scale = renderer.dpi / 96.0;
+
+	// The size is validated by the builder, but the raster is allocated in memory so it's checked again before allocating it
+	let (width, height) = super::png_size(renderer.width, renderer.height, renderer.dpi).ok_or(png::EncodingError::LimitsExceeded)?;
+	let mut raster = Raster::new(width as usize, height as usize, renderer.background).ok_or(png::EncodingError::LimitsExceeded)?;
+	for (color, shapes) in renderer.layout(waveform, scale) {
+		for shape in &shapes {
+			raster.draw(shape, color);
+		}
+	}
+
+	let mut encoder = png::Encoder::new(output, width, height);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let pixels_per_metre = (renderer.dpi * INCHES_PER_METRE).round() as u32;
+	encoder.set_pixel_dims(Some(png::PixelDimensions {
+		xppu: pixels_per_metre,
+		yppu: pixels_per_metre,
+		unit: png::Unit::Meter,
+	}));
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(&raster.pixels)?;
+	writer.finish()?;
+
+	Ok(())
+}
@@ -0,0 +1,71 @@
+//! Writes the [`Shape`]s of a waveform as an SVG image
+
+use super::{Color, Renderer, Shape};
+use crate::Waveform;
+use std::io::Write;
+
+/// Writes the `fill` or `stroke` attribute of a colour, with its opacity if it is not opaque
+fn paint(output: &mut impl Write, attribute: &str, color: Color) -> Result<(), std::io::Error> {
+	write!(output, " {attribute}=\"#{:02x}{:02x}{:02x}\"", color.r, color.g, color.b)?;
+
+	if color.a != u8::MAX {
+		write!(output, r#" {attribute}-opacity="{:.3}""#, color.a as f64 / 255.0)?;
+	}
+
+	Ok(())
+}
+
+pub(super) fn render(renderer: &Renderer, waveform: &Waveform, mut output: impl Write) -> Result<(), std::io::Error> {
+	let (width, height) = (renderer.width, renderer.height);
+
+	writeln!(
+		output,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+	)?;
+
+	if let Some(background) = renderer.background {
+		write!(output, r#"<rect width="100%" height="100%""#)?;
+		paint(&mut output, "fill", background)?;
+		writeln!(output, "/>")?;
+	}
+
+	for (color, shapes) in renderer.layout(waveform, 1.0) {
+		write!(output, "<g")?;
+		paint(&mut output, "fill", color)?;
+		writeln!(output, ">")?;
+
+		for shape in shapes {
+			match shape {
+				Shape::Rect { x, y, width, height } => {
+					writeln!(output, r#"<rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{height:.2}"/>"#)?;
+				}
+
+				Shape::Area(columns) => {
+					// The outline runs along the tops of the columns and back along their bottoms
+					write!(output, r#"<path d=""#)?;
+					for (i, (x, top, _)) in columns.iter().enumerate() {
+						write!(output, "{}{x:.2},{top:.2} {:.2},{top:.2}", if i == 0 { "M" } else { " L" }, x + 1.0)?;
+					}
+					for (x, _, bottom) in columns.iter().rev() {
+						write!(output, " L{:.2},{bottom:.2} {x:.2},{bottom:.2}", x + 1.0)?;
+					}
+					writeln!(output, r#"Z"/>"#)?;
+				}
+
+				Shape::Line { points, thickness } => {
+					write!(output, r#"<polyline fill="none" stroke-width="{thickness:.2}" stroke-linejoin="round""#)?;
+					paint(&mut output, "stroke", color)?;
+					write!(output, r#" points=""#)?;
+					for (i, (x, y)) in points.iter().enumerate() {
+						write!(output, "{}{x:.2},{y:.2}", if i == 0 { "" } else { " " })?;
+					}
+					writeln!(output, r#""/>"#)?;
+				}
+			}
+		}
+
+		writeln!(output, "</g>")?;
+	}
+
+	writeln!(output, "</svg>")
+}
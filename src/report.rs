@@ -0,0 +1,48 @@
+//! The result of generating a waveform
+
+use crate::{Channel, ChannelStats};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+/// The result of generating a waveform
+pub struct Report {
+	/// The path to the output file
+	pub output: PathBuf,
+
+	/// The duration of the audio stream, in seconds
+	pub duration: f64,
+
+	/// Statistics for each channel of the audio stream, if [`stats`](crate::FfAudio2JsonBuilder::stats) was enabled
+	pub stats: Option<Vec<ChannelStats>>,
+
+	/// The phase correlation between the left and right channels of the whole stream, in the range of -1.0 to 1.0,
+	/// if [`correlation`](crate::FfAudio2JsonBuilder::correlation) was enabled
+	pub correlation: Option<f64>,
+
+	/// The generated points, if [`keep_points`](crate::FfAudio2JsonBuilder::keep_points) was enabled
+	pub waveform: Option<Waveform>,
+
+	/// Whether generation was skipped because the output was up to date, in which case only the output and duration are known
+	///
+	/// See [`cache`](crate::FfAudio2JsonBuilder::cache).
+	pub cached: bool,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+/// The points of a generated waveform
+pub struct Waveform {
+	/// The points of each channel, in the order that the channels were requested
+	///
+	/// Points are in the range of 0.0 to 1.0, and are scaled in the same way as in the output.
+	pub channels: Vec<(Channel, Vec<f64>)>,
+
+	/// The time in seconds of the audio stream that the first point starts at
+	///
+	/// This is only non-zero if leading silence was trimmed.
+	pub start: f64,
+
+	/// The duration in seconds of the audio that the points were generated from
+	pub duration: f64,
+}
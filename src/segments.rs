@@ -0,0 +1,118 @@
+//! Decoding segments of a single stream in parallel
+
+use crate::{
+	analysis::Analysis,
+	channels::{ChannelWriter, Channels},
+	generator::GeneratorContext,
+	Error, FfAudio2Json,
+};
+use std::{num::NonZeroUsize, ops::Range};
+
+/// How far before the start of a segment decoding starts, in seconds
+///
+/// Codecs need some audio before the start of a segment to produce the same samples as when the stream is decoded from the start,
+/// for example to fill the bit reservoir of MP3 or the overlapping windows of AAC and Opus.
+const PREROLL: f64 = 1.0;
+
+impl FfAudio2Json {
+	/// The number of segments to decode in parallel, for a waveform of `points` points
+	pub(crate) fn segment_count(&self, points: u64) -> u64 {
+		let segments = match self.segments {
+			0 => std::thread::available_parallelism().map_or(1, NonZeroUsize::get) as u64,
+			segments => segments as u64,
+		};
+
+		// Every segment has at least one point
+		segments.min(points).max(1)
+	}
+
+	/// Splits `window` into `segments` ranges of samples that each start on the boundary of a point, and decodes them in parallel
+	///
+	/// The points of the segments are then written to `writers` in the same order as if the stream had been decoded serially.
+	pub(crate) fn generate_segments(
+		&self,
+		mut writers: Channels<ChannelWriter>,
+		window: &Range<u64>,
+		samples_per_point: u64,
+		points: u64,
+		segments: u64,
+	) -> Result<Channels<ChannelWriter>, Error> {
+		// Decoding stops once all the points have been written, which is where the last segment ends
+		let end = window.end.min(window.start + points * samples_per_point);
+
+		let ranges = (0..segments)
+			.map(|segment| {
+				let first_point = points * segment / segments;
+				let start = window.start + first_point * samples_per_point;
+
+				if segment + 1 == segments {
+					start..end
+				} else {
+					start..window.start + points * (segment + 1) / segments * samples_per_point
+				}
+			})
+			.collect::<Vec<_>>();
+
+		log::debug!("Decoding {segments} segments in parallel...");
+
+		let decoded = std::thread::scope(|scope| {
+			let handles = ranges
+				.into_iter()
+				.map(|range| scope.spawn(move || self.generate_segment(range, samples_per_point as usize)))
+				.collect::<Vec<_>>();
+
+			handles
+				.into_iter()
+				.map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+				.collect::<Result<Vec<_>, _>>()
+		})?;
+
+		// Write the points point by point rather than channel by channel, in the same order as the serial decoder writes them
+		for mut segment in decoded {
+			let len = segment.iter_mut().map(|writer| writer.as_points().len()).next().unwrap_or(0);
+
+			for point in 0..len {
+				for channel in self.channels.iter().copied() {
+					if let (Some(writer), Some(points)) = (writers.get_mut(channel), segment.get(channel)) {
+						// The segments don't have more points than the waveform between them, so the writers never stop early
+						let _ = writer.write(points.as_points()[point], self)?;
+					}
+				}
+			}
+		}
+
+		Ok(writers)
+	}
+
+	/// Decodes the samples in `range` into points kept in memory
+	fn generate_segment(&self, range: Range<u64>, samples_per_point: usize) -> Result<Channels<ChannelWriter>, Error> {
+		let (mut ictx, stream_idx, _, mut decoder) = self.open_input()?;
+
+		let points = (range.end - range.start).div_ceil(samples_per_point as u64) as usize;
+
+		let mut writers = Channels::<ChannelWriter>::default();
+		for channel in self.channels.iter().copied() {
+			writers.set(channel, ChannelWriter::points(points));
+		}
+
+		let seek = (range.start > 0).then(|| range.start.saturating_sub((PREROLL * decoder.rate() as f64) as u64));
+
+		let (writers, _) = GeneratorContext::generate(
+			GeneratorContext {
+				writers,
+				buffer_capacity: samples_per_point,
+				config: self,
+				stream_idx,
+				analysis: Analysis::inactive(),
+				window: range,
+				progress: None,
+				duration: 0.0,
+				seek,
+			},
+			&mut ictx,
+			&mut decoder,
+		)?;
+
+		Ok(writers)
+	}
+}
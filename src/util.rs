@@ -2,6 +2,16 @@ pub fn map2range(x: f64, in_min: f64, in_max: f64, out_min: f64, out_max: f64) -
 	(out_min + (out_max - out_min) * (x - in_min) / (in_max - in_min)).clamp(out_min, out_max)
 }
 
+/// Convert a linear amplitude to decibels
+pub fn to_db(amplitude: f64) -> f64 {
+	20.0 * amplitude.log10()
+}
+
+/// Convert decibels to a linear amplitude
+pub fn from_db(db: f64) -> f64 {
+	10.0_f64.powf(db / 20.0)
+}
+
 macro_rules! unwrap_break {
 	($expr:expr) => {
 		if let cflow @ ControlFlow::Break(()) = $expr {
@@ -30,3 +30,36 @@ macro_rules! enable_logging {
 			.ok();
 	};
 }
+
+/// Writes 16-bit PCM samples, interleaved by channel, to a WAV file in the test data directory and returns its path
+///
+/// For tests that need audio with exactly known sample values.
+#[macro_export]
+macro_rules! write_wav {
+	($path:literal, $sample_rate:expr, $channels:expr, $samples:expr) => {{
+		let samples: &[i16] = &$samples;
+		let channels: u16 = $channels;
+		let sample_rate: u32 = $sample_rate;
+		let data_size = samples.len() as u32 * 2;
+
+		let mut wav = Vec::with_capacity(44 + data_size as usize);
+		wav.extend_from_slice(b"RIFF");
+		wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+		wav.extend_from_slice(b"WAVEfmt ");
+		wav.extend_from_slice(&16_u32.to_le_bytes());
+		wav.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+		wav.extend_from_slice(&channels.to_le_bytes());
+		wav.extend_from_slice(&sample_rate.to_le_bytes());
+		wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes()); // Byte rate
+		wav.extend_from_slice(&(channels * 2).to_le_bytes()); // Block align
+		wav.extend_from_slice(&16_u16.to_le_bytes()); // Bits per sample
+		wav.extend_from_slice(b"data");
+		wav.extend_from_slice(&data_size.to_le_bytes());
+		for sample in samples {
+			wav.extend_from_slice(&sample.to_le_bytes());
+		}
+
+		::std::fs::write(path!($path), wav).unwrap();
+		path!($path)
+	}};
+}
@@ -0,0 +1,50 @@
+use ffaudio2json::{Channel, OutputFormat};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_audiowaveform_json() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo_audiowaveform.wav.json")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.format(OutputFormat::AudiowaveformJson)
+		.bits(8)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_audiowaveform.wav.json");
+	let json = json.as_object().unwrap();
+
+	let keys = json.keys().map(String::as_str).collect::<Vec<_>>();
+	assert_eq!(
+		keys,
+		["bits", "channels", "data", "length", "sample_rate", "samples_per_pixel", "version"]
+	);
+
+	assert_eq!(json.get("version").unwrap().as_u64().unwrap(), 2);
+	assert_eq!(json.get("channels").unwrap().as_u64().unwrap(), 2);
+	assert_eq!(json.get("bits").unwrap().as_u64().unwrap(), 8);
+	assert!(json.get("sample_rate").unwrap().as_u64().unwrap() > 0);
+	assert!(json.get("samples_per_pixel").unwrap().as_u64().unwrap() > 0);
+
+	let length = json.get("length").unwrap().as_u64().unwrap() as usize;
+	assert_eq!(length, 100);
+
+	let data = json.get("data").unwrap().as_array().unwrap();
+	assert_eq!(data.len(), length * 2 * 2);
+
+	for pair in data.chunks_exact(2) {
+		let min = pair[0].as_i64().unwrap();
+		let max = pair[1].as_i64().unwrap();
+		assert!((-127..=127).contains(&min));
+		assert!((-127..=127).contains(&max));
+		assert!(min <= max);
+	}
+}
@@ -0,0 +1,49 @@
+use ffaudio2json::{Channel, Error, FfAudio2Json};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_batch() {
+	enable_logging!();
+
+	let config = |input, output| {
+		FfAudio2Json::builder()
+			.input(input)
+			.output(Some(output))
+			.samples(200)
+			.channels(vec![Channel::Left, Channel::Right])
+			.build()
+			.unwrap()
+	};
+
+	let configs = vec![
+		config(path!("SecondSummerYliStereo.mp3"), path!("SecondSummerYliStereo_batch_0.mp3.json")),
+		config(path!("does_not_exist.mp3"), path!("does_not_exist_batch.mp3.json")),
+		config(path!("SecondSummerYliStereo.mp3"), path!("SecondSummerYliStereo_batch_2.mp3.json")),
+		config(path!("SecondSummerYliStereo.mp3"), path!("SecondSummerYliStereo_batch_3.mp3.json")),
+	];
+
+	let mut results = Vec::new();
+	FfAudio2Json::run_batch(configs, 3, |index, result| results.push((index, result)));
+
+	assert_eq!(results.iter().map(|(index, _)| *index).collect::<Vec<_>>(), [0, 1, 2, 3]);
+	assert!(matches!(results[1].1, Err(Error::Io(_))));
+
+	config(path!("SecondSummerYliStereo.mp3"), path!("SecondSummerYliStereo_batch.mp3.json"))
+		.run()
+		.unwrap();
+	let expected = open_json!("SecondSummerYliStereo_batch.mp3.json");
+
+	for (index, path) in [
+		(0, "SecondSummerYliStereo_batch_0.mp3.json"),
+		(2, "SecondSummerYliStereo_batch_2.mp3.json"),
+		(3, "SecondSummerYliStereo_batch_3.mp3.json"),
+	] {
+		let report = results[index].1.as_ref().unwrap();
+		assert_eq!(report.output.file_name().unwrap(), path);
+
+		let json = serde_json::from_reader::<_, serde_json::Value>(std::fs::File::open(&report.output).unwrap()).unwrap();
+		assert_eq!(json, expected);
+	}
+}
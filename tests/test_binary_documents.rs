@@ -0,0 +1,77 @@
+use ffaudio2json::{Channel, OutputFormat};
+
+#[macro_use]
+mod common;
+
+fn assert_same_document(document: &serde_json::Value, json: &serde_json::Value) {
+	let document = document.as_object().unwrap();
+	let json = json.as_object().unwrap();
+
+	assert_eq!(
+		document.keys().collect::<Vec<_>>(),
+		json.keys().collect::<Vec<_>>(),
+		"documents have different keys"
+	);
+
+	for channel in ["left", "right", "mid"] {
+		let points = document.get(channel).unwrap().as_array().unwrap();
+		let expected = json.get(channel).unwrap().as_array().unwrap();
+		assert_eq!(points.len(), expected.len());
+
+		for (point, expected) in points.iter().zip(expected) {
+			assert!((point.as_f64().unwrap() - expected.as_f64().unwrap()).abs() < 0.00001);
+		}
+	}
+
+	assert_eq!(document.get("_generator"), json.get("_generator"));
+	assert!((document.get("duration").unwrap().as_f64().unwrap() - json.get("duration").unwrap().as_f64().unwrap()).abs() < 0.00001);
+	assert_eq!(
+		document.get("silence").unwrap().as_array().unwrap().len(),
+		json.get("silence").unwrap().as_array().unwrap().len()
+	);
+}
+
+fn generate(format: OutputFormat, output: std::path::PathBuf) {
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(output))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right, Channel::Mid])
+		.detect_silence(true)
+		.silence_min_duration(0.1)
+		.format(format)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+}
+
+#[test]
+fn test_msgpack() {
+	enable_logging!();
+
+	generate(OutputFormat::Json, path!("SecondSummerYliStereo_msgpack.mp3.json"));
+	generate(OutputFormat::MessagePack, path!("SecondSummerYliStereo.mp3.msgpack"));
+
+	let document: serde_json::Value = rmp_serde::from_read(std::io::BufReader::new(
+		std::fs::File::open(path!("SecondSummerYliStereo.mp3.msgpack")).unwrap(),
+	))
+	.unwrap();
+
+	assert_same_document(&document, &open_json!("SecondSummerYliStereo_msgpack.mp3.json"));
+}
+
+#[test]
+fn test_cbor() {
+	enable_logging!();
+
+	generate(OutputFormat::Json, path!("SecondSummerYliStereo_cbor.mp3.json"));
+	generate(OutputFormat::Cbor, path!("SecondSummerYliStereo.mp3.cbor"));
+
+	let document: serde_json::Value = ciborium::from_reader(std::io::BufReader::new(
+		std::fs::File::open(path!("SecondSummerYliStereo.mp3.cbor")).unwrap(),
+	))
+	.unwrap();
+
+	assert_same_document(&document, &open_json!("SecondSummerYliStereo_cbor.mp3.json"));
+}
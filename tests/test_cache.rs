@@ -0,0 +1,60 @@
+#![cfg(feature = "cache")]
+
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_cache() {
+	enable_logging!();
+
+	let generate = |samples, force| {
+		ffaudio2json::FfAudio2Json::builder()
+			.input(path!("SecondSummerYliStereo.mp3"))
+			.output(Some(path!("SecondSummerYliStereo_cache.mp3.json")))
+			.samples(samples)
+			.channels(vec![Channel::Left, Channel::Right])
+			.cache(true)
+			.force(force)
+			.build()
+			.unwrap()
+			.run_with_report()
+			.unwrap()
+	};
+
+	std::fs::remove_file(path!("SecondSummerYliStereo_cache.mp3.json")).ok();
+	std::fs::remove_file(path!("SecondSummerYliStereo_cache.mp3.json.fingerprint")).ok();
+
+	let report = generate(100, false);
+	assert!(!report.cached);
+	assert!(path!("SecondSummerYliStereo_cache.mp3.json.fingerprint").exists());
+	let expected = std::fs::read(path!("SecondSummerYliStereo_cache.mp3.json")).unwrap();
+
+	let cached = generate(100, false);
+	assert!(cached.cached);
+	assert_eq!(cached.duration, report.duration);
+	assert_eq!(std::fs::read(path!("SecondSummerYliStereo_cache.mp3.json")).unwrap(), expected);
+
+	// The output is generated again if it is forced, the options change or it is missing
+	assert!(!generate(100, true).cached);
+	assert!(!generate(50, false).cached);
+	assert!(generate(50, false).cached);
+
+	std::fs::remove_file(path!("SecondSummerYliStereo_cache.mp3.json")).unwrap();
+	assert!(!generate(50, false).cached);
+
+	// An output that was replaced without the cache isn't up to date, even though the fingerprint matches
+	assert!(!generate(100, false).cached);
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_cache.mp3.json")))
+		.samples(50)
+		.channels(vec![Channel::Left, Channel::Right])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+	assert!(!generate(100, false).cached);
+	assert_eq!(std::fs::read(path!("SecondSummerYliStereo_cache.mp3.json")).unwrap(), expected);
+}
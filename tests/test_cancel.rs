@@ -0,0 +1,36 @@
+use ffaudio2json::{Channel, Error};
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_cancel() {
+	enable_logging!();
+
+	let cancel = Arc::new(AtomicBool::new(false));
+
+	let result = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_cancel.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right])
+		.cancel(Some(cancel.clone()))
+		.progress({
+			let cancel = cancel.clone();
+			move |progress| {
+				if progress.fraction > 0.5 {
+					cancel.store(true, Ordering::Relaxed);
+				}
+			}
+		})
+		.build()
+		.unwrap()
+		.run();
+
+	assert!(matches!(result, Err(Error::Cancelled)), "{result:?}");
+	assert!(!path!("SecondSummerYliStereo_cancel.mp3.json").exists());
+}
@@ -0,0 +1,103 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_clipping() {
+	enable_logging!();
+
+	// One second of quiet stereo audio at 8 kHz, with runs of full scale samples at known positions
+	let mut samples = (0..8000)
+		.flat_map(|i| {
+			let sample = ((i as f64 / 8.0).sin() * 1000.0) as i16;
+			[sample, -sample]
+		})
+		.collect::<Vec<_>>();
+
+	let mut clip = |channel: usize, positions: std::ops::Range<usize>, sample: i16| {
+		for position in positions {
+			samples[position * 2 + channel] = sample;
+		}
+	};
+
+	clip(0, 1000..1100, i16::MAX);
+	clip(0, 4000..4010, i16::MIN);
+
+	// A single full scale sample is shorter than the minimum run, so it isn't clipping
+	clip(0, 6000..6001, i16::MAX);
+
+	// A run that lasts until the end of the stream
+	clip(1, 7990..8000, i16::MIN);
+
+	let input = write_wav!("clipping.generated.wav", 8000, 2, samples);
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(input)
+		.output(Some(path!("clipping.generated.wav.json")))
+		.samples(10)
+		.channels(vec![Channel::Left, Channel::Right])
+		.detect_clipping(true)
+		.clipping_min_samples(2)
+		.clipped_channel(true)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("clipping.generated.wav.json");
+	let json = json.as_object().unwrap();
+
+	let clipping = json.get("clipping").expect("clipping missing").as_object().unwrap();
+	assert_eq!(clipping.get("count").unwrap().as_u64(), Some(3));
+
+	let channels = clipping.get("channels").unwrap().as_array().unwrap();
+	assert_eq!(channels.len(), 2);
+
+	let regions = |channel: usize| {
+		channels[channel]
+			.get("regions")
+			.unwrap()
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|region| {
+				(
+					region.get("start").unwrap().as_f64().unwrap(),
+					region.get("end").unwrap().as_f64().unwrap(),
+				)
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let assert_regions = |channel: usize, expected: &[(f64, f64)]| {
+		let regions = regions(channel);
+		assert_eq!(regions.len(), expected.len(), "{regions:?}");
+		for ((start, end), (expected_start, expected_end)) in regions.into_iter().zip(expected) {
+			assert!((start - expected_start).abs() < 1e-9, "start {start} != {expected_start}");
+			assert!((end - expected_end).abs() < 1e-9, "end {end} != {expected_end}");
+		}
+	};
+
+	assert_regions(0, &[(1000.0 / 8000.0, 1100.0 / 8000.0), (4000.0 / 8000.0, 4010.0 / 8000.0)]);
+	assert_regions(1, &[(7990.0 / 8000.0, 1.0)]);
+
+	assert_eq!(channels[0].get("count").unwrap().as_u64(), Some(2));
+	assert_eq!(channels[0].get("samples").unwrap().as_u64(), Some(110));
+	assert_eq!(channels[1].get("count").unwrap().as_u64(), Some(1));
+	assert_eq!(channels[1].get("samples").unwrap().as_u64(), Some(10));
+
+	// Each point covers 800 samples
+	let clipped = json
+		.get("clipped")
+		.expect("clipped missing")
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|count| count.as_u64().unwrap())
+		.collect::<Vec<_>>();
+
+	assert_eq!(clipped, [0, 100, 0, 0, 0, 10, 0, 0, 0, 10]);
+	assert_eq!(clipped.len(), json.get("left").unwrap().as_array().unwrap().len());
+}
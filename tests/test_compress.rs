@@ -0,0 +1,94 @@
+#![cfg(feature = "compress")]
+
+use ffaudio2json::{Channel, Compression, OutputFormat};
+use std::io::Read;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_compress() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_uncompressed.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let expected = std::fs::read(path!("SecondSummerYliStereo_uncompressed.mp3.json")).unwrap();
+
+	for compression in [Compression::Gzip, Compression::Brotli, Compression::Zstd] {
+		let output = ffaudio2json::FfAudio2Json::builder()
+			.input(path!("SecondSummerYliStereo.mp3"))
+			.samples(800)
+			.channels(vec![Channel::Left, Channel::Right])
+			.compress(Some(compression))
+			.build()
+			.unwrap()
+			.run()
+			.unwrap();
+
+		assert_eq!(
+			output.file_name().unwrap().to_str().unwrap(),
+			format!("SecondSummerYliStereo.mp3.json.{}", compression.extension())
+		);
+		assert!(!output.with_extension(format!("{}.uncompressed", compression.extension())).exists());
+
+		let file = std::fs::File::open(&output).unwrap();
+		let mut decompressed = Vec::new();
+
+		match compression {
+			Compression::Gzip => flate2::read::GzDecoder::new(file).read_to_end(&mut decompressed),
+			Compression::Brotli => brotli::Decompressor::new(file, 4096).read_to_end(&mut decompressed),
+			Compression::Zstd => zstd::Decoder::new(file).unwrap().read_to_end(&mut decompressed),
+		}
+		.unwrap();
+
+		assert_eq!(decompressed, expected, "{compression}");
+	}
+}
+
+#[test]
+fn test_compress_sequential() {
+	enable_logging!();
+
+	// These formats are compressed as they're written, rather than once they have been generated
+	for format in [OutputFormat::Dat, OutputFormat::Csv] {
+		let generate = |output, compression| {
+			ffaudio2json::FfAudio2Json::builder()
+				.input(path!("SecondSummerYliStereo.mp3"))
+				.output(Some(output))
+				.samples(800)
+				.channels(vec![Channel::Left, Channel::Right])
+				.format(format)
+				.compress(compression)
+				.build()
+				.unwrap()
+				.run()
+				.unwrap()
+		};
+
+		let expected = std::fs::read(generate(path!("SecondSummerYliStereo_sequential.mp3.out"), None)).unwrap();
+
+		for compression in [Compression::Gzip, Compression::Brotli, Compression::Zstd] {
+			let output = generate(path!("SecondSummerYliStereo_sequential.mp3.out.compressed"), Some(compression));
+
+			let file = std::fs::File::open(&output).unwrap();
+			let mut decompressed = Vec::new();
+
+			match compression {
+				Compression::Gzip => flate2::read::GzDecoder::new(file).read_to_end(&mut decompressed),
+				Compression::Brotli => brotli::Decompressor::new(file, 4096).read_to_end(&mut decompressed),
+				Compression::Zstd => zstd::Decoder::new(file).unwrap().read_to_end(&mut decompressed),
+			}
+			.unwrap();
+
+			assert_eq!(decompressed, expected, "{format} {compression}");
+		}
+	}
+}
@@ -0,0 +1,97 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_correlation_stereo() {
+	enable_logging!();
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_correlation.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Side])
+		.correlation(true)
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	let json = open_json!("SecondSummerYliStereo_correlation.mp3.json");
+	let json = json.as_object().unwrap();
+
+	let correlation = json.get("correlation").expect("correlation missing").as_array().unwrap();
+	assert_eq!(correlation.len(), json.get("side").unwrap().as_array().unwrap().len());
+	assert!(correlation
+		.iter()
+		.map(|point| point.as_f64().unwrap())
+		.all(|point| (-1.0..=1.0).contains(&point)));
+
+	let total = json.get("correlation_total").expect("correlation_total missing").as_f64().unwrap();
+	assert!((-1.0..=1.0).contains(&total));
+	assert!((report.correlation.unwrap() - total).abs() < 1e-9);
+}
+
+#[test]
+fn test_correlation_mono() {
+	enable_logging!();
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliMono.wav"))
+		.output(Some(path!("SecondSummerYliMono_correlation.wav.json")))
+		.samples(800)
+		.channels(vec![Channel::Mid])
+		.correlation(true)
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	// Both "sides" of a mono file are identical
+	assert!((report.correlation.unwrap() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_correlation_whole_stream() {
+	enable_logging!();
+
+	// With 1999 samples and 1000 points, each point is a single sample and the points are complete halfway through the stream.
+	// The second half is out of phase, so it must still be measured after the points are complete.
+	let samples = (0..1999)
+		.flat_map(|i| {
+			let left = ((i as f64 / 5.0).sin() * 8000.0) as i16;
+			let right = if i < 1000 { left } else { -left / 2 };
+			[left, right]
+		})
+		.collect::<Vec<_>>();
+
+	let input = write_wav!("correlation.generated.wav", 8000, 2, samples);
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(input)
+		.output(Some(path!("correlation.generated.wav.json")))
+		.samples(1000)
+		.channels(vec![Channel::Left, Channel::Right])
+		.correlation(true)
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	let (left_right, left_left, right_right) = samples.chunks(2).fold((0.0, 0.0, 0.0), |(lr, ll, rr), pair| {
+		let (left, right) = (pair[0] as f64, pair[1] as f64);
+		(lr + left * right, ll + left * left, rr + right * right)
+	});
+	let expected = left_right / (left_left * right_right).sqrt();
+
+	let json = open_json!("correlation.generated.wav.json");
+	let total = json.get("correlation_total").expect("correlation_total missing").as_f64().unwrap();
+
+	assert!((total - expected).abs() < 1e-9, "{total} != {expected}");
+	assert!((report.correlation.unwrap() - expected).abs() < 1e-9);
+	assert_eq!(json.get("correlation").unwrap().as_array().unwrap().len(), 1000);
+}
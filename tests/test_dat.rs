@@ -0,0 +1,112 @@
+use ffaudio2json::{Channel, OutputFormat};
+
+#[macro_use]
+mod common;
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+	i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn test_dat_mono() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("airboat_gun_lastshot1_1khz_mono.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_mono.wav.dat")))
+		.samples(100)
+		.channels(vec![Channel::Left])
+		.format(OutputFormat::Dat)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let data = std::fs::read(output).unwrap();
+
+	// Version 1 header, 16-bit
+	assert_eq!(read_i32(&data, 0), 1);
+	assert_eq!(read_i32(&data, 4), 0);
+	assert!(read_i32(&data, 8) > 0);
+	assert!(read_i32(&data, 12) > 0);
+
+	let length = read_i32(&data, 16) as usize;
+	assert_eq!(length, 100);
+	assert_eq!(data.len(), 20 + length * 2 * 2);
+
+	for pair in data[20..].chunks_exact(4) {
+		let min = i16::from_le_bytes([pair[0], pair[1]]);
+		let max = i16::from_le_bytes([pair[2], pair[3]]);
+		assert!(min <= max);
+	}
+}
+
+#[test]
+fn test_dat_stereo_8bit() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.mp3"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo.mp3.dat")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.format(OutputFormat::Dat)
+		.bits(8)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let data = std::fs::read(output).unwrap();
+
+	// Version 2 header, 8-bit, 2 channels
+	assert_eq!(read_i32(&data, 0), 2);
+	assert_eq!(read_i32(&data, 4), 1);
+	assert_eq!(read_i32(&data, 20), 2);
+
+	let length = read_i32(&data, 16) as usize;
+	assert_eq!(length, 100);
+	assert_eq!(data.len(), 24 + length * 2 * 2);
+
+	for pair in data[24..].chunks_exact(2) {
+		assert!(pair[0] as i8 <= pair[1] as i8);
+	}
+}
+
+#[test]
+fn test_dat_default_extension() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliMono.mp3"))
+		.samples(10)
+		.format(OutputFormat::Dat)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	assert_eq!(output, path!("SecondSummerYliMono.mp3.dat"));
+}
+
+#[test]
+fn test_dat_invalid_bits() {
+	assert!(ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliMono.mp3"))
+		.bits(12)
+		.build()
+		.is_err());
+}
+
+#[test]
+fn test_dat_trim_silence() {
+	// The audiowaveform formats have nowhere to record the trimmed region
+	for format in [OutputFormat::Dat, OutputFormat::AudiowaveformJson] {
+		assert!(ffaudio2json::FfAudio2Json::builder()
+			.input(path!("SecondSummerYliMono.mp3"))
+			.format(format)
+			.trim_silence(true)
+			.build()
+			.is_err());
+	}
+}
@@ -0,0 +1,54 @@
+use ffaudio2json::{Channel, JsonStyle};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_json_styles() {
+	enable_logging!();
+
+	for (style, path) in [
+		(JsonStyle::Compact, path!("SecondSummerYliStereo_compact.mp3.json")),
+		(JsonStyle::Pretty, path!("SecondSummerYliStereo_pretty.mp3.json")),
+		(JsonStyle::Minified, path!("SecondSummerYliStereo_minified.mp3.json")),
+	] {
+		ffaudio2json::FfAudio2Json::builder()
+			.no_header(true)
+			.input(path!("SecondSummerYliStereo.mp3"))
+			.output(Some(path))
+			.samples(100)
+			.precision(3)
+			.channels(vec![Channel::Left, Channel::Right])
+			.stats(true)
+			.json_style(style)
+			.build()
+			.unwrap()
+			.run()
+			.unwrap();
+	}
+
+	let compact = std::fs::read_to_string(path!("SecondSummerYliStereo_compact.mp3.json")).unwrap();
+	let pretty = std::fs::read_to_string(path!("SecondSummerYliStereo_pretty.mp3.json")).unwrap();
+	let minified = std::fs::read_to_string(path!("SecondSummerYliStereo_minified.mp3.json")).unwrap();
+
+	assert_eq!(
+		open_json!("SecondSummerYliStereo_compact.mp3.json"),
+		open_json!("SecondSummerYliStereo_pretty.mp3.json")
+	);
+	assert_eq!(
+		open_json!("SecondSummerYliStereo_compact.mp3.json"),
+		open_json!("SecondSummerYliStereo_minified.mp3.json")
+	);
+
+	// Each of the 4 keys of the outermost object is on its own line
+	assert_json_spaces!("SecondSummerYliStereo_compact.mp3.json", 8);
+	assert_eq!(compact.lines().count(), 6);
+
+	assert!(!minified.contains([' ', '\n']));
+	assert_eq!(minified.len(), compact.len() - 13);
+
+	assert!(pretty.starts_with("{\n  \"left\": [0."));
+	assert!(pretty.contains("\n  \"stats\": [\n    {\n      \""));
+	assert!(pretty.ends_with("\n}"));
+	assert!(!pretty.contains("  \n") && !pretty.contains("   ,"));
+}
@@ -0,0 +1,167 @@
+use ffaudio2json::{Channel, OutputFormat};
+use std::{
+	io::BufRead,
+	sync::{Arc, Mutex},
+};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_ndjson() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo.mp3.ndjson")))
+		.samples(800)
+		.precision(3)
+		.chunk_size(100)
+		.stats(true)
+		.channels(vec![Channel::Left, Channel::Right])
+		.format(OutputFormat::Ndjson)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_ndjson.mp3.json")))
+		.samples(800)
+		.precision(3)
+		.stats(true)
+		.channels(vec![Channel::Left, Channel::Right])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let records = std::io::BufReader::new(std::fs::File::open(output).unwrap())
+		.lines()
+		.map(|line| serde_json::from_str::<serde_json::Value>(&line.unwrap()).unwrap())
+		.collect::<Vec<_>>();
+
+	let header = records.first().unwrap();
+	assert_eq!(header["type"], "header");
+	assert_eq!(header["channels"], serde_json::json!(["left", "right"]));
+	assert_eq!(header["points"], 800);
+
+	let footer = records.last().unwrap();
+	assert_eq!(footer["type"], "footer");
+	assert_eq!(footer["points"], 800);
+
+	let json = open_json!("SecondSummerYliStereo_ndjson.mp3.json");
+	assert_eq!(footer["duration"], json["duration"]);
+	assert_eq!(footer["stats"], json["stats"]);
+
+	for channel in ["left", "right"] {
+		let mut points = Vec::new();
+
+		for record in records[1..records.len() - 1].iter().filter(|record| record["channel"] == channel) {
+			assert_eq!(record["type"], "points");
+			assert_eq!(record["start"], points.len());
+
+			let chunk = record["points"].as_array().unwrap();
+			assert!(chunk.len() <= 100);
+			points.extend(chunk.iter().cloned());
+		}
+
+		assert_eq!(&points, json[channel].as_array().unwrap());
+	}
+}
+
+#[test]
+fn test_ndjson_streaming() {
+	enable_logging!();
+
+	let output = path!("SecondSummerYliStereo_streaming.mp3.ndjson");
+	std::fs::remove_file(&output).ok();
+
+	// The records that were already in the output while it was being generated
+	let records = Arc::new(Mutex::new(None));
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(output.clone()))
+		.samples(800)
+		.chunk_size(10)
+		.channels(vec![Channel::Left, Channel::Right])
+		.format(OutputFormat::Ndjson)
+		.stream_in_place(true)
+		.progress({
+			let output = output.clone();
+			let records = records.clone();
+
+			move |progress| {
+				let mut records = records.lock().unwrap();
+				if records.is_none() && progress.points >= 100 && progress.fraction < 1.0 {
+					let lines = std::fs::read_to_string(&output)
+						.unwrap()
+						.lines()
+						.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+						.collect::<Vec<_>>();
+
+					*records = Some(lines);
+				}
+			}
+		})
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let records = records
+		.lock()
+		.unwrap()
+		.take()
+		.expect("progress wasn't reported while points were generated");
+
+	assert_eq!(records[0]["type"], "header");
+	assert!(records.len() > 2, "{records:?}");
+	assert!(records[1..].iter().all(|record| record["type"] == "points"));
+}
+
+#[test]
+fn test_ndjson_atomic() {
+	enable_logging!();
+
+	let output = path!("SecondSummerYliStereo_atomic.mp3.ndjson");
+	std::fs::write(&output, "previous\n").unwrap();
+
+	// Whether the previous output was still intact while the new one was being generated
+	let intact = Arc::new(Mutex::new(None));
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(output.clone()))
+		.samples(800)
+		.chunk_size(10)
+		.channels(vec![Channel::Left, Channel::Right])
+		.format(OutputFormat::Ndjson)
+		.progress({
+			let output = output.clone();
+			let intact = intact.clone();
+
+			move |progress| {
+				let mut intact = intact.lock().unwrap();
+				if intact.is_none() && progress.points >= 100 && progress.fraction < 1.0 {
+					*intact = Some(std::fs::read_to_string(&output).unwrap() == "previous\n");
+				}
+			}
+		})
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	assert_eq!(*intact.lock().unwrap(), Some(true));
+	assert!(std::fs::read_to_string(&output).unwrap().starts_with(r#"{"type":"header""#));
+
+	// Streaming in place can't be combined with compression, or with formats that are only complete once they're finished
+	assert!(ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.stream_in_place(true)
+		.build()
+		.is_err());
+}
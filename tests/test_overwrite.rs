@@ -0,0 +1,41 @@
+use ffaudio2json::{Channel, Error};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_no_overwrite() {
+	enable_logging!();
+
+	let generate = |no_overwrite| {
+		ffaudio2json::FfAudio2Json::builder()
+			.input(path!("SecondSummerYliStereo.mp3"))
+			.output(Some(path!("SecondSummerYliStereo_overwrite.mp3.json")))
+			.samples(100)
+			.channels(vec![Channel::Left, Channel::Right])
+			.no_overwrite(no_overwrite)
+			.build()
+			.unwrap()
+			.run()
+	};
+
+	std::fs::remove_file(path!("SecondSummerYliStereo_overwrite.mp3.json")).ok();
+
+	generate(true).unwrap();
+	let expected = std::fs::read(path!("SecondSummerYliStereo_overwrite.mp3.json")).unwrap();
+
+	let result = generate(true);
+	assert!(matches!(result, Err(Error::OutputExists(_))), "{result:?}");
+	assert_eq!(std::fs::read(path!("SecondSummerYliStereo_overwrite.mp3.json")).unwrap(), expected);
+
+	generate(false).unwrap();
+	assert_eq!(std::fs::read(path!("SecondSummerYliStereo_overwrite.mp3.json")).unwrap(), expected);
+
+	// No temporary files are left behind next to the output
+	let leftovers = std::fs::read_dir(path!(""))
+		.unwrap()
+		.filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+		.filter(|name| name.starts_with(".SecondSummerYliStereo_overwrite") && name.ends_with(".tmp"))
+		.collect::<Vec<_>>();
+	assert!(leftovers.is_empty(), "{leftovers:?}");
+}
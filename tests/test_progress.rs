@@ -0,0 +1,44 @@
+use ffaudio2json::{Channel, Progress};
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_progress() {
+	enable_logging!();
+
+	let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_progress.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right])
+		.progress({
+			let reports = reports.clone();
+			move |progress| reports.lock().unwrap().push(*progress)
+		})
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	let reports = reports.lock().unwrap();
+	assert!(reports.len() > 2);
+
+	for pair in reports.windows(2) {
+		assert!(pair[1].time >= pair[0].time);
+		assert!(pair[1].fraction >= pair[0].fraction);
+		assert!(pair[1].points >= pair[0].points);
+		assert!(pair[1].bytes_read >= pair[0].bytes_read);
+	}
+
+	let last = reports.last().unwrap();
+	assert_eq!(last.fraction, 1.0);
+	assert_eq!(last.points, 800);
+	assert!((last.time - report.duration).abs() < 0.1);
+
+	let input_size = std::fs::metadata(path!("SecondSummerYliStereo.mp3")).unwrap().len();
+	assert!(last.bytes_read > 0 && last.bytes_read <= input_size);
+}
@@ -0,0 +1,88 @@
+use base64::Engine;
+use ffaudio2json::{Channel, PointEncoding};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_quantized_integer() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_integer.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right])
+		.encoding(PointEncoding::Integer)
+		.bits(8)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("SecondSummerYliStereo_integer.mp3.json");
+	let json = json.as_object().unwrap();
+
+	assert_eq!(json.get("scale").unwrap().as_u64().unwrap(), 255);
+
+	for channel in ["left", "right"] {
+		let points = json.get(channel).unwrap().as_array().unwrap();
+		assert_eq!(points.len(), 800);
+		assert!(points.iter().all(|point| point.as_u64().is_some_and(|point| point <= 255)));
+	}
+}
+
+#[test]
+fn test_quantized_base64() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_base64.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Mid])
+		.encoding(PointEncoding::Base64)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_decimal.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Mid])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("SecondSummerYliStereo_base64.mp3.json");
+	let json = json.as_object().unwrap();
+
+	let decimal = open_json!("SecondSummerYliStereo_decimal.mp3.json");
+	let decimal = decimal.as_object().unwrap();
+
+	assert_eq!(json.get("scale").unwrap().as_u64().unwrap(), 65535);
+
+	for channel in ["left", "mid"] {
+		let bytes = base64::engine::general_purpose::STANDARD
+			.decode(json.get(channel).unwrap().as_str().unwrap())
+			.unwrap();
+
+		let points = bytes
+			.chunks_exact(2)
+			.map(|point| u16::from_le_bytes([point[0], point[1]]))
+			.collect::<Vec<_>>();
+
+		let expected = decimal.get(channel).unwrap().as_array().unwrap();
+		assert_eq!(points.len(), expected.len());
+
+		for (point, expected) in points.iter().zip(expected) {
+			assert!((*point as f64 / 65535.0 - expected.as_f64().unwrap()).abs() < 0.0001);
+		}
+	}
+}
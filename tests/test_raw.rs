@@ -0,0 +1,165 @@
+use ffaudio2json::{Channel, OutputFormat};
+
+#[macro_use]
+mod common;
+
+struct Header {
+	format: u8,
+	channels: Vec<String>,
+	points: usize,
+	sample_rate: u32,
+	duration: f64,
+	start: f64,
+	end: f64,
+	size: usize,
+}
+
+fn read_header(data: &[u8]) -> Header {
+	assert_eq!(&data[0..4], b"FA2W");
+	assert_eq!(u16::from_le_bytes([data[4], data[5]]), 1);
+
+	let channel_count = data[7] as usize;
+	let channel_list_len = u16::from_le_bytes([data[40], data[41]]) as usize;
+
+	let channels = std::str::from_utf8(&data[42..42 + channel_list_len])
+		.unwrap()
+		.split(',')
+		.map(ToString::to_string)
+		.collect::<Vec<_>>();
+
+	assert_eq!(channels.len(), channel_count);
+
+	Header {
+		format: data[6],
+		channels,
+		points: u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize,
+		sample_rate: u32::from_le_bytes(data[12..16].try_into().unwrap()),
+		duration: f64::from_le_bytes(data[16..24].try_into().unwrap()),
+		start: f64::from_le_bytes(data[24..32].try_into().unwrap()),
+		end: f64::from_le_bytes(data[32..40].try_into().unwrap()),
+		size: (42 + channel_list_len).next_multiple_of(8),
+	}
+}
+
+#[test]
+fn test_raw_float32() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo.mp3.bin")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right, Channel::Side])
+		.format(OutputFormat::Float32)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_raw.mp3.json")))
+		.samples(800)
+		.channels(vec![Channel::Left, Channel::Right, Channel::Side])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let data = std::fs::read(output).unwrap();
+	let header = read_header(&data);
+
+	assert_eq!(header.format, 0);
+	assert_eq!(header.channels, ["left", "right", "side"]);
+	assert_eq!(header.points, 800);
+	assert!(header.sample_rate > 0);
+	assert!(header.duration > 0.0);
+	assert_eq!(header.size % 8, 0);
+	assert_eq!(data.len(), header.size + 3 * header.points * 4);
+
+	let json = open_json!("SecondSummerYliStereo_raw.mp3.json");
+	let json = json.as_object().unwrap();
+
+	assert!((json.get("duration").unwrap().as_f64().unwrap() - header.duration).abs() < 0.001);
+	assert_eq!(header.start, 0.0);
+	assert_eq!(header.end, header.duration);
+
+	for (n, channel) in header.channels.iter().enumerate() {
+		let offset = header.size + n * header.points * 4;
+
+		let points = data[offset..offset + header.points * 4]
+			.chunks_exact(4)
+			.map(|point| f32::from_le_bytes(point.try_into().unwrap()));
+
+		let expected = json.get(channel.as_str()).unwrap().as_array().unwrap();
+		assert_eq!(expected.len(), header.points);
+
+		for (point, expected) in points.zip(expected) {
+			assert!((point as f64 - expected.as_f64().unwrap()).abs() < 0.00001);
+		}
+	}
+}
+
+#[test]
+fn test_raw_uint8() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("airboat_gun_lastshot1_1khz_mono.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_mono.wav.bin")))
+		.samples(100)
+		.channels(vec![Channel::Mid])
+		.format(OutputFormat::Uint8)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let data = std::fs::read(output).unwrap();
+	let header = read_header(&data);
+
+	assert_eq!(header.format, 1);
+	assert_eq!(header.channels, ["mid"]);
+	assert_eq!(header.points, 100);
+	assert_eq!(data.len(), header.size + header.points);
+	assert!(data[header.size..].iter().any(|point| *point > 0));
+}
+
+#[test]
+fn test_raw_trim_silence() {
+	enable_logging!();
+
+	let generate = |format, output| {
+		ffaudio2json::FfAudio2Json::builder()
+			.no_header(true)
+			.input(path!("airboat_gun_lastshot1_1khz_stereo.wav"))
+			.output(Some(output))
+			.samples(100)
+			.channels(vec![Channel::Left])
+			.format(format)
+			.trim_silence(true)
+			.trim_silence_threshold(-30.0)
+			.build()
+			.unwrap()
+			.run()
+			.unwrap()
+	};
+
+	let output = generate(OutputFormat::Float32, path!("airboat_gun_lastshot1_1khz_stereo_trim_silence.wav.bin"));
+	generate(OutputFormat::Json, path!("airboat_gun_lastshot1_1khz_stereo_raw_trim_silence.wav.json"));
+
+	let data = std::fs::read(output).unwrap();
+	let header = read_header(&data);
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_raw_trim_silence.wav.json");
+	let trim = json.get("trim").expect("trim missing");
+	let (start, end) = (trim.get("start").unwrap().as_f64().unwrap(), trim.get("end").unwrap().as_f64().unwrap());
+
+	// The header records where the points are in the stream, so that they can be mapped back to it
+	assert!(start > 0.0);
+	assert!((header.start - start).abs() < 1e-9, "{} != {start}", header.start);
+	assert!((header.end - end).abs() < 1e-9, "{} != {end}", header.end);
+	assert!((header.duration - (end - start)).abs() < 1e-9);
+	assert!(header.duration < json.get("duration").unwrap().as_f64().unwrap());
+}
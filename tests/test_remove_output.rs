@@ -0,0 +1,34 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_remove_output() {
+	enable_logging!();
+
+	let config = || {
+		ffaudio2json::FfAudio2Json::builder()
+			.input(path!("SecondSummerYliMono.mp3"))
+			.output(Some(path!("SecondSummerYliMono_remove.mp3.json")))
+			.samples(100)
+			.channels(vec![Channel::Left])
+			.cache(cfg!(feature = "cache"))
+			.build()
+			.unwrap()
+	};
+
+	config().run().unwrap();
+	assert!(path!("SecondSummerYliMono_remove.mp3.json").exists());
+
+	#[cfg(feature = "cache")]
+	assert!(path!("SecondSummerYliMono_remove.mp3.json.fingerprint").exists());
+
+	assert_eq!(config().remove_output().unwrap(), Some(path!("SecondSummerYliMono_remove.mp3.json")));
+	assert!(!path!("SecondSummerYliMono_remove.mp3.json").exists());
+
+	#[cfg(feature = "cache")]
+	assert!(!path!("SecondSummerYliMono_remove.mp3.json.fingerprint").exists());
+
+	assert_eq!(config().remove_output().unwrap(), None);
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "render")]
+
+use ffaudio2json::{
+	render::{Color, RenderStyle, Renderer},
+	Channel,
+};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_render() {
+	enable_logging!();
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_render.mp3.json")))
+		.samples(400)
+		.channels(vec![Channel::Left, Channel::Right])
+		.keep_points(true)
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	let waveform = report.waveform.unwrap();
+	assert_eq!(waveform.channels.len(), 2);
+
+	let json = open_json!("SecondSummerYliStereo_render.mp3.json");
+	for (channel, points) in &waveform.channels {
+		let expected = json.get(channel.to_string()).unwrap().as_array().unwrap();
+		assert_eq!(points.len(), expected.len());
+
+		for (point, expected) in points.iter().zip(expected) {
+			assert!((point - expected.as_f64().unwrap()).abs() < 1e-5);
+		}
+	}
+
+	let renderer = Renderer::builder()
+		.width(400)
+		.height(100)
+		.dpi(192.0)
+		.background(Some(Color::rgb(0xff, 0xff, 0xff)))
+		.build()
+		.unwrap();
+
+	let mut png = Vec::new();
+	renderer.render_png(&waveform, &mut png).unwrap();
+
+	assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+	assert_eq!(&png[12..16], b"IHDR");
+	assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 800);
+	assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 200);
+
+	for style in [RenderStyle::Bars, RenderStyle::Line, RenderStyle::Filled] {
+		let renderer = Renderer::builder()
+			.width(400)
+			.height(100)
+			.style(style)
+			.colors(vec![Color::rgba(0xff, 0, 0, 0x80)])
+			.build()
+			.unwrap();
+
+		let mut svg = Vec::new();
+		renderer.render_svg(&waveform, &mut svg).unwrap();
+
+		let svg = String::from_utf8(svg).unwrap();
+		assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="100""#));
+		assert!(svg.trim_end().ends_with("</svg>"));
+		assert_eq!(svg.matches("<g ").count(), 2);
+		assert_eq!(svg.matches(r##"fill="#ff0000" fill-opacity="0.502""##).count(), 2);
+	}
+
+	assert!(Renderer::builder().width(0).build().is_err());
+	assert!(Renderer::builder().colors(Vec::new()).build().is_err());
+
+	// Images that are too large to render in memory are rejected rather than allocated
+	assert!(Renderer::builder().dpi(10000.0).build().is_err());
+	assert!(Renderer::builder().width(u32::MAX).height(u32::MAX).build().is_err());
+	assert!(Renderer::builder().width(8192).height(8192).build().is_ok());
+}
@@ -0,0 +1,43 @@
+use ffaudio2json::{Channel, FfAudio2Json};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_segments() {
+	enable_logging!();
+
+	for (input, serial, segmented) in [
+		(
+			path!("SecondSummerYliMono.wav"),
+			path!("SecondSummerYliMono_serial.wav.json"),
+			path!("SecondSummerYliMono_segments.wav.json"),
+		),
+		(
+			path!("SecondSummerYliStereo.mp3"),
+			path!("SecondSummerYliStereo_serial.mp3.json"),
+			path!("SecondSummerYliStereo_segments.mp3.json"),
+		),
+	] {
+		for (segments, output) in [(1, &serial), (4, &segmented)] {
+			FfAudio2Json::builder()
+				.input(input.clone())
+				.output(Some(output.clone()))
+				.samples(800)
+				.precision(3)
+				.channels(vec![Channel::Left, Channel::Right, Channel::Mid])
+				.segments(segments)
+				.build()
+				.unwrap()
+				.run()
+				.unwrap();
+		}
+
+		assert_eq!(
+			std::fs::read_to_string(&serial).unwrap(),
+			std::fs::read_to_string(&segmented).unwrap(),
+			"{}",
+			input.display()
+		);
+	}
+}
@@ -0,0 +1,67 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_silence_everything_below_threshold() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo_silence_all.wav.json")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.detect_silence(true)
+		.silence_threshold(6.0)
+		.silence_min_duration(0.0)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_silence_all.wav.json");
+	let json = json.as_object().unwrap();
+
+	let silence = json.get("silence").expect("silence missing").as_array().unwrap();
+	assert_eq!(silence.len(), 1);
+	assert_eq!(silence[0].get("start").unwrap().as_f64().unwrap(), 0.0);
+
+	let end = silence[0].get("end").unwrap().as_f64().unwrap();
+	let duration = json.get("duration").unwrap().as_f64().unwrap();
+	assert!((end - duration).abs() < 0.1, "{end} != {duration}");
+}
+
+#[test]
+fn test_silence_per_channel() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.flac"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo_silence_per_channel.flac.json")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.detect_silence(true)
+		.silence_min_duration(0.1)
+		.silence_per_channel(true)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_silence_per_channel.flac.json");
+	let json = json.as_object().unwrap();
+
+	let silence = json.get("silence").expect("silence missing").as_array().unwrap();
+	assert_eq!(silence.len(), 2);
+
+	for intervals in silence {
+		for interval in intervals.as_array().unwrap() {
+			let start = interval.get("start").unwrap().as_f64().unwrap();
+			let end = interval.get("end").unwrap().as_f64().unwrap();
+			assert!(end - start >= 0.1 - f64::EPSILON, "{start}..{end} is shorter than the minimum duration");
+		}
+	}
+}
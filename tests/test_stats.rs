@@ -0,0 +1,43 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_stats() {
+	enable_logging!();
+
+	let report = ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo_stats.wav.json")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.stats(true)
+		.build()
+		.unwrap()
+		.run_with_report()
+		.unwrap();
+
+	let stats = report.stats.expect("stats missing");
+	assert_eq!(stats.len(), 2);
+
+	for stats in &stats {
+		assert!(stats.samples > 0);
+		assert!(stats.peak_level <= 0.0, "peak level {} above full scale", stats.peak_level);
+		assert!(stats.rms_level <= stats.peak_level);
+		assert!(stats.crest_factor >= 1.0);
+		assert!((0.0..=1.0).contains(&stats.zero_crossing_rate));
+	}
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_stats.wav.json");
+	let json = json.as_object().unwrap();
+
+	let json_stats = json.get("stats").expect("stats missing").as_array().unwrap();
+	assert_eq!(json_stats.len(), 2);
+
+	for (json_stats, stats) in json_stats.iter().zip(&stats) {
+		assert_eq!(json_stats.get("samples").unwrap().as_u64().unwrap(), stats.samples);
+		assert!((json_stats.get("peak_level").unwrap().as_f64().unwrap() - stats.peak_level).abs() < 1e-9);
+	}
+}
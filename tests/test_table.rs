@@ -0,0 +1,88 @@
+use ffaudio2json::{Channel, OutputFormat};
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_csv() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo.mp3.csv")))
+		.samples(800)
+		.precision(3)
+		.channels(vec![Channel::Left, Channel::Right, Channel::Side])
+		.format(OutputFormat::Csv)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.input(path!("SecondSummerYliStereo.mp3"))
+		.output(Some(path!("SecondSummerYliStereo_csv.mp3.json")))
+		.samples(800)
+		.precision(3)
+		.channels(vec![Channel::Left, Channel::Right, Channel::Side])
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let csv = std::fs::read_to_string(output).unwrap();
+	let mut rows = csv.lines();
+
+	assert_eq!(rows.next(), Some("time,left,right,side"));
+
+	let json = open_json!("SecondSummerYliStereo_csv.mp3.json");
+	let json = json.as_object().unwrap();
+	let duration = json.get("duration").unwrap().as_f64().unwrap();
+
+	let rows = rows.collect::<Vec<_>>();
+	assert_eq!(rows.len(), 800);
+
+	let mut last_time = -1.0;
+	for (i, row) in rows.iter().enumerate() {
+		let columns = row.split(',').collect::<Vec<_>>();
+		assert_eq!(columns.len(), 4);
+
+		let time = columns[0].parse::<f64>().unwrap();
+		assert!(time > last_time && time < duration);
+		last_time = time;
+
+		for (column, channel) in columns[1..].iter().zip(["left", "right", "side"]) {
+			assert_eq!(column.split_once('.').unwrap().1.len(), 3);
+			let expected = json.get(channel).unwrap().as_array().unwrap()[i].as_f64().unwrap();
+			assert!((column.parse::<f64>().unwrap() - expected).abs() < 0.0001);
+		}
+	}
+}
+
+#[test]
+fn test_tsv_db_scale() {
+	enable_logging!();
+
+	let output = ffaudio2json::FfAudio2Json::builder()
+		.input(path!("airboat_gun_lastshot1_1khz_mono.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_mono.wav.tsv")))
+		.samples(100)
+		.db_scale(true)
+		.channels(vec![Channel::Mid])
+		.format(OutputFormat::Tsv)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let tsv = std::fs::read_to_string(output).unwrap();
+	let mut rows = tsv.lines();
+
+	assert_eq!(rows.next(), Some("time\tmid"));
+	assert!(rows.next().unwrap().starts_with("0.000000\t"));
+
+	for row in rows {
+		let (_, mid) = row.split_once('\t').unwrap();
+		assert!((0.0..=1.0).contains(&mid.parse::<f64>().unwrap()));
+	}
+}
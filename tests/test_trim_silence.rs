@@ -0,0 +1,64 @@
+use ffaudio2json::Channel;
+
+#[macro_use]
+mod common;
+
+#[test]
+fn test_trim_silence() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("airboat_gun_lastshot1_1khz_stereo.wav"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_stereo_trim_silence.wav.json")))
+		.samples(100)
+		.channels(vec![Channel::Left, Channel::Right])
+		.trim_silence(true)
+		.trim_silence_threshold(-30.0)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_stereo_trim_silence.wav.json");
+	let json = json.as_object().unwrap();
+
+	let trim = json.get("trim").expect("trim missing").as_object().unwrap();
+	let start = trim.get("start").unwrap().as_f64().unwrap();
+	let end = trim.get("end").unwrap().as_f64().unwrap();
+	let duration = json.get("duration").unwrap().as_f64().unwrap();
+
+	assert!(0.0 <= start && start < end && end <= duration + 0.1, "0 <= {start} < {end} <= {duration}");
+
+	assert_eq!(json.get("left").unwrap().as_array().unwrap().len(), 100);
+	assert_eq!(json.get("right").unwrap().as_array().unwrap().len(), 100);
+}
+
+#[test]
+fn test_trim_silence_nothing_audible() {
+	enable_logging!();
+
+	ffaudio2json::FfAudio2Json::builder()
+		.no_header(true)
+		.input(path!("airboat_gun_lastshot1_1khz_mono.flac"))
+		.output(Some(path!("airboat_gun_lastshot1_1khz_mono_trim_silence.flac.json")))
+		.samples(100)
+		.channels(vec![Channel::Mid])
+		.trim_silence(true)
+		.trim_silence_threshold(6.0)
+		.build()
+		.unwrap()
+		.run()
+		.unwrap();
+
+	let json = open_json!("airboat_gun_lastshot1_1khz_mono_trim_silence.flac.json");
+	let json = json.as_object().unwrap();
+
+	// Nothing is above the threshold, so nothing is trimmed
+	let trim = json.get("trim").expect("trim missing").as_object().unwrap();
+	assert_eq!(trim.get("start").unwrap().as_f64().unwrap(), 0.0);
+
+	let end = trim.get("end").unwrap().as_f64().unwrap();
+	let duration = json.get("duration").unwrap().as_f64().unwrap();
+	assert!((end - duration).abs() < 0.1, "{end} != {duration}");
+}